@@ -0,0 +1,140 @@
+//! Requests that rspotify doesn't provide, or doesn't provide all the parameters of.
+
+use lazy_static::lazy_static;
+use reqwest::{blocking::Client, Method};
+use rspotify::{
+    blocking::client::{ApiError, Spotify},
+    model::{
+        playing::PlayHistory,
+        track::{FullTrack, SimplifiedTrack},
+        PlayingItem,
+    },
+};
+use serde::{de::DeserializeOwned, Deserialize};
+
+type Result<T> = ::std::result::Result<T, failure::Error>;
+
+const API_URL: &str = "https://api.spotify.com/v1/";
+
+lazy_static! {
+    // shared so the connections are reused between calls
+    static ref HTTP: Client = Client::new();
+}
+
+/// The player queue.
+#[derive(Debug, Deserialize)]
+pub struct Queue {
+    pub currently_playing: Option<PlayingItem>,
+    pub queue: Vec<PlayingItem>,
+}
+
+/// A page of the recently played tracks.
+#[derive(Debug, Deserialize)]
+pub struct History {
+    pub items: Vec<PlayHistory>,
+    pub next: Option<String>,
+    pub cursors: Option<HistoryCursors>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HistoryCursors {
+    pub after: Option<String>,
+    pub before: Option<String>,
+}
+
+fn token(client: &Spotify) -> String {
+    match (&client.access_token, &client.client_credentials_manager) {
+        (Some(token), _) => token.clone(),
+        (None, Some(m)) => m.get_access_token(),
+        (None, None) => String::new(),
+    }
+}
+
+fn call(
+    client: &Spotify,
+    method: Method,
+    path: &str,
+    query: &[(&str, String)],
+) -> Result<reqwest::blocking::Response> {
+    let mut req = HTTP
+        .request(method.clone(), &format!("{}{}", API_URL, path))
+        .bearer_auth(token(client))
+        .query(query);
+    if method != Method::GET {
+        // the api rejects bodyless requests without a length
+        req = req.header(reqwest::header::CONTENT_LENGTH, 0);
+    }
+    let resp = req.send()?;
+    if resp.status().is_success() {
+        Ok(resp)
+    } else {
+        Err(ApiError::from(resp).into())
+    }
+}
+
+/// Whether a player request failed because no device is active.
+pub fn is_no_active_device(e: &failure::Error) -> bool {
+    match e.downcast_ref::<ApiError>() {
+        Some(ApiError::PlayerError { reason, .. }) => reason == "NO_ACTIVE_DEVICE",
+        // player errors are usually read as regular ones, without the reason
+        Some(ApiError::RegularError { status, message }) => {
+            *status == 404 && message.contains("No active device")
+        }
+        _ => false,
+    }
+}
+
+pub fn get<T: DeserializeOwned>(
+    client: &Spotify,
+    path: &str,
+    query: &[(&str, String)],
+) -> Result<T> {
+    Ok(call(client, Method::GET, path, query)?.json()?)
+}
+
+pub fn put(client: &Spotify, path: &str, query: &[(&str, String)]) -> crate::SpotifyResult {
+    call(client, Method::PUT, path, query).map(|_| ())
+}
+
+#[derive(Debug, Deserialize)]
+struct Named {
+    name: String,
+}
+
+/// Looks up the name of an album, artist, playlist or show.
+pub fn name_of(client: &Spotify, kind: &str, id: &str) -> Result<String> {
+    let path = format!("{}s/{}", kind, id);
+    let query = if kind == "playlist" {
+        vec![("fields", String::from("name"))]
+    } else {
+        Vec::new()
+    };
+    get::<Named>(client, &path, &query).map(|n| n.name)
+}
+
+/// The currently playing item and the ones that will play after it.
+pub fn queue(client: &Spotify) -> Result<Queue> {
+    get(client, "me/player/queue", &[])
+}
+
+/// Like `Spotify::current_user_recently_played` but it can page back in time.
+pub fn recently_played(client: &Spotify, limit: u32, before: Option<&str>) -> Result<History> {
+    let mut query = vec![("limit", limit.to_string())];
+    if let Some(b) = before {
+        query.push(("before", b.to_string()));
+    }
+    get(client, "me/player/recently-played", &query)
+}
+
+/// Fetches the full versions of the tracks, 50 at a time.
+pub fn full_tracks(client: &Spotify, tracks: &[SimplifiedTrack]) -> Result<Vec<FullTrack>> {
+    let mut full = Vec::with_capacity(tracks.len());
+    for chunk in tracks.chunks(50) {
+        let ids = chunk
+            .iter()
+            .map(|t| t.id.as_deref().unwrap_or(&t.uri))
+            .collect();
+        full.extend(client.tracks(ids, None)?.tracks);
+    }
+    Ok(full)
+}
@@ -0,0 +1,63 @@
+//! Keeps the user token fresh for as long as the process runs.
+
+use chrono::Utc;
+use rspotify::blocking::{
+    client::Spotify,
+    oauth2::{SpotifyClientCredentials, SpotifyOAuth, TokenInfo},
+    util::get_token,
+};
+use std::sync::{Arc, Mutex};
+
+/// How long before it expires the token is refreshed.
+const REFRESH_MARGIN_SECS: i64 = 300;
+
+/// The user token, shared with the background tasks.
+/// An expired token isn't refreshed by the client, it quietly falls back to an app token
+/// that can't read or control the player.
+#[derive(Debug, Clone)]
+pub struct Session {
+    oauth: SpotifyOAuth,
+    token: Arc<Mutex<TokenInfo>>,
+}
+
+impl Session {
+    /// Uses the cached token, or asks to log in if there is none. `None` if that fails.
+    pub fn new(mut oauth: SpotifyOAuth) -> Option<Self> {
+        let token = get_token(&mut oauth)?;
+        Some(Self {
+            oauth,
+            token: Arc::new(Mutex::new(token)),
+        })
+    }
+
+    /// A client with a token that is valid for a few more minutes at least.
+    pub fn client(&self) -> Result<Spotify, failure::Error> {
+        let mut token = self
+            .token
+            .lock()
+            .map_err(|_| failure::err_msg("the token is poisoned"))?;
+        let expires_soon = token
+            .expires_at
+            .map_or(true, |t| Utc::now().timestamp() > t - REFRESH_MARGIN_SECS);
+        if expires_soon {
+            let refresh = token
+                .refresh_token
+                .clone()
+                .ok_or_else(|| failure::err_msg("the token can't be refreshed, log in again"))?;
+            // the new token is written to the cache as well
+            *token = self
+                .oauth
+                .refresh_access_token(&refresh)
+                .ok_or_else(|| failure::err_msg("could not refresh the token"))?;
+        }
+
+        let credentials = SpotifyClientCredentials::default()
+            .client_id(&self.oauth.client_id)
+            .client_secret(&self.oauth.client_secret)
+            .token_info(token.clone())
+            .build();
+        Ok(Spotify::default()
+            .client_credentials_manager(credentials)
+            .build())
+    }
+}
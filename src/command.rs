@@ -6,41 +6,102 @@ pub enum Cmd {
     SearchArtist,
     SearchAlbum,
     SearchPlaylist,
+    SearchShow,
+    SearchEpisode,
 
     // play-first commands (similar to "i'm feeling lucky")
     PlayFirstTrack,
     PlayFirstAlbum,
     PlayFirstPlaylist,
     PlayFirstArtist,
+    PlayFirstEpisode,
 
     // player commands
+    ShowQueue,
     SetVolume,
     Shuffle,
     Repeat,
     Next,
     Prev,
+    Seek,
+    SeekForward,
+    SeekBackward,
+    Resume,
+    Sleep,
+    At,
 
     // library commands
     SavePlaying,
     RemovePlaying,
+    CopyTracks,
+    MoveTracks,
+    Where,
+    Smart,
+    Liked,
+    LikePlaying,
+    UnlikePlaying,
+    SavedAlbums,
+    FollowedArtists,
+    SavedShows,
     CreatePlaylist,
     EditPlaylist,
     DeletePlaylist,
+    SortPlaylist,
+    ShufflePlaylist,
+    Merge,
+    Intersect,
+    Subtract,
+
+    // discovery commands
+    Recommend,
+    Features,
+    History,
+    Top,
+    Stats,
 
     // misc
     Help,
     PlayUserPlaylist,
     SetDevice,
     Show,
+    Watch,
     Prompt,
 }
 
+impl Cmd {
+    /// Whether the command opens a shell or asks for input, so it can't run unattended.
+    /// Some commands only ask when they're not given what to act on.
+    pub fn is_interactive(self, args: Option<&str>) -> bool {
+        use Cmd::*;
+        match self {
+            Search | SearchTrack | SearchArtist | SearchAlbum | SearchPlaylist | SearchShow
+            | SearchEpisode | ShowQueue | At | CopyTracks | MoveTracks | SavedAlbums
+            | FollowedArtists | SavedShows | CreatePlaylist | EditPlaylist | DeletePlaylist
+            | SortPlaylist | ShufflePlaylist | Merge | Intersect | Subtract | Recommend
+            | History | Top | Watch => true,
+            SavePlaying | PlayUserPlaylist | SetDevice => args.is_none(),
+            // removing it everywhere asks for confirmation
+            RemovePlaying => matches!(args, None | Some("--all")),
+            // only `liked play` doesn't open the list
+            Liked => !args.map_or(false, |a| a.trim().eq_ignore_ascii_case("play")),
+            _ => false,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum TrackCmd {
     Play,
     Queue,
     Save,
     Like,
+    Unlike,
+    QueueAll,
+    SaveAll,
+    Features,
+    Filter,
+    More,
+    Back,
     Help,
 }
 
@@ -49,6 +110,10 @@ pub enum AlbumCmd {
     Play,
     Queue,
     Save,
+    Unsave,
+    Tracks,
+    More,
+    Back,
     Help,
 }
 
@@ -56,6 +121,32 @@ pub enum AlbumCmd {
 pub enum ArtistCmd {
     Play,
     Follow,
+    Unfollow,
+    Top,
+    Albums,
+    Related,
+    More,
+    Back,
+    Help,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShowCmd {
+    Play,
+    Episodes,
+    Save,
+    Unsave,
+    Back,
+    Help,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EpisodeCmd {
+    Play,
+    Resume,
+    Queue,
+    Save,
+    Back,
     Help,
 }
 
@@ -63,5 +154,9 @@ pub enum ArtistCmd {
 pub enum PlaylistCmd {
     Play,
     Follow,
+    Unfollow,
+    Tracks,
+    Clone,
+    Back,
     Help,
 }
@@ -0,0 +1,33 @@
+use crate::{hook::Hook, listens::ListeningLog, smart::SmartPlaylist};
+use serde::Deserialize;
+use std::{collections::HashMap, fs, io, path::Path};
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub smart_playlists: Vec<SmartPlaylist>,
+    /// Template of the card shown by `show`, the placeholders are listed in the readme.
+    pub now_playing: Option<String>,
+    /// Run when the playback changes, while libman is open.
+    pub hooks: Vec<Hook>,
+    /// Logs what you listen to while libman is open, off if not set.
+    pub listening_log: Option<ListeningLog>,
+    /// Short names for devices, used by `device`.
+    pub device_aliases: HashMap<String, String>,
+}
+
+impl Config {
+    /// Reads the config from a json file, a missing file is the same as an empty config.
+    pub fn load(path: &Path) -> Result<Self, failure::Error> {
+        let config: Self = match fs::read_to_string(path) {
+            Ok(s) => serde_json::from_str(&s)
+                .map_err(|e| failure::format_err!("{}: {}", path.display(), e))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(failure::format_err!("{}: {}", path.display(), e)),
+        };
+        match config.hooks.iter().find_map(|h| h.validate().err()) {
+            Some(e) => Err(failure::format_err!("{}: {}", path.display(), e)),
+            None => Ok(config),
+        }
+    }
+}
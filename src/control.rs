@@ -1,50 +1,96 @@
+mod discover_cmd;
+mod library_cmd;
+mod player_cmd;
+mod podcast_cmd;
+mod schedule_cmd;
 pub mod search_cmd;
+mod smart_cmd;
+
+use search_cmd::{Noted, TrackList};
 
 use crate::{
-    command::{AlbumCmd, ArtistCmd, Cmd, PlaylistCmd, TrackCmd},
+    api,
+    auth::Session,
+    command::{AlbumCmd, ArtistCmd, Cmd, EpisodeCmd, PlaylistCmd, ShowCmd, TrackCmd},
+    config::Config,
     handler::{self, Handler},
-    playlist::Playlist,
+    index::LibraryIndex,
+    listens,
+    playlist::{self, Playlist, SetOp, SortKey},
     prompt, read_bool, read_input, read_number, read_option, read_option_bool, search,
-    split_command, SpotifyResult,
+    split_command,
+    state::State,
+    task::Task,
+    watcher, SpotifyResult,
 };
 
 use chrono::Utc;
+use rand::seq::SliceRandom;
 use regex::Regex;
 
 use rspotify::{
     blocking::client::Spotify,
     model::{
-        album::SimplifiedAlbum, artist::FullArtist, device::Device, offset::Offset,
-        playlist::PlaylistTrack, track::FullTrack, PlayingItem,
+        album::{FullAlbum, SimplifiedAlbum},
+        artist::FullArtist,
+        audio::AudioFeatures,
+        device::Device,
+        offset::{self, Offset},
+        playlist::PlaylistTrack,
+        track::{FullTrack, SavedTrack, SimplifiedTrack},
+        PlayingItem,
     },
     senum::{AdditionalType, RepeatState},
 };
-use std::{convert::TryFrom, mem};
+use std::{
+    cell::RefCell, collections::HashMap, convert::TryFrom, mem, path::PathBuf, thread,
+    time::Duration,
+};
 
 pub struct Controller {
     client: Spotify,
+    /// Hands out clients with a fresh token, `client` is replaced before the token expires.
+    session: Session,
     user: String,
     handlers: Vec<Handler<Cmd>>,
     track_handlers: Vec<Handler<TrackCmd>>,
     artist_handlers: Vec<Handler<ArtistCmd>>,
     album_handlers: Vec<Handler<AlbumCmd>>,
     playlist_handlers: Vec<Handler<PlaylistCmd>>,
+    show_handlers: Vec<Handler<ShowCmd>>,
+    episode_handlers: Vec<Handler<EpisodeCmd>>,
     prompt: String,
     playing: bool,
     last_pl: Option<Playlist>,
     pl_cache: Option<Vec<Playlist>>,
     device: Option<String>,
+    config: Config,
+    /// Labels of the nested shells currently open, shown in their prompt.
+    crumbs: Vec<String>,
+    /// Built on demand by `library_index`.
+    index: Option<LibraryIndex>,
+    /// Uris added to the playing queue during this session.
+    queued: RefCell<Vec<String>>,
+    /// Jobs running in the background, like the sleep timer.
+    tasks: Vec<Task>,
+    schedule_path: PathBuf,
+    /// Where the preferred device is remembered.
+    state_path: PathBuf,
 }
 
 impl Controller {
     pub fn new(
-        client: Spotify,
+        session: Session,
         user_id: String,
         handlers: Vec<Handler<Cmd>>,
         prompt: String,
-    ) -> Self {
-        Self {
-            client,
+        config: Config,
+        schedule_path: PathBuf,
+        state_path: PathBuf,
+    ) -> Result<Self, failure::Error> {
+        Ok(Self {
+            client: session.client()?,
+            session,
             prompt,
             user: user_id,
             handlers,
@@ -52,41 +98,56 @@ impl Controller {
             artist_handlers: handler::default_artist_handlers(),
             album_handlers: handler::default_album_handlers(),
             playlist_handlers: handler::default_playlist_handlers(),
+            show_handlers: handler::default_show_handlers(),
+            episode_handlers: handler::default_episode_handlers(),
             playing: false,
             last_pl: None,
             pl_cache: None,
             device: None,
-        }
+            config,
+            crumbs: Vec::new(),
+            index: None,
+            queued: RefCell::new(Vec::new()),
+            tasks: Vec::new(),
+            schedule_path,
+            state_path,
+        })
     }
 
     pub fn start(&mut self) {
-        let re_vol = Regex::new(r"^\s*(\-|\+)\s*(\d+)\s*$").unwrap();
+        self.start_watcher();
         loop {
+            self.reap_tasks();
+            self.print_reports();
             let input = prompt(&self.prompt);
-
-            if let Err(e) = if input.is_empty() {
-                self.toggle()
-            } else if let Some(cap) = re_vol.captures(&input) {
-                let op = cap.get(1).unwrap().as_str();
-                let n = cap.get(2).unwrap().as_str().parse::<i32>().unwrap();
-                self.change_volume(if op == "+" { n } else { -n })
-            } else {
-                // check handlers
-                let (cmd, args) = split_command(&input);
-                let cmd = match self.handlers.iter().find(|h| h.is_match(cmd)) {
-                    None => {
-                        println!("{} is not a known command", cmd);
-                        continue;
-                    }
-                    Some(h) => h.cmd,
-                };
-                self.exec_cmd(cmd, args)
-            } {
+            if let Err(e) = self.refresh_client().and_then(|_| self.run(&input)) {
                 println!("error: {}", e);
             }
         }
     }
 
+    /// Runs a line the way it would be run if typed in the prompt.
+    fn run(&mut self, input: &str) -> SpotifyResult {
+        let re_vol = Regex::new(r"^\s*(\-|\+)\s*(\d+)\s*$").unwrap();
+        if input.is_empty() {
+            self.toggle()
+        } else if let Some(cap) = re_vol.captures(input) {
+            let op = cap.get(1).unwrap().as_str();
+            let n = cap.get(2).unwrap().as_str().parse::<i32>().unwrap();
+            self.change_volume(if op == "+" { n } else { -n })
+        } else {
+            // check handlers
+            let (cmd, args) = split_command(input);
+            match self.handlers.iter().find(|h| h.is_match(cmd)) {
+                None => {
+                    println!("{} is not a known command", cmd);
+                    Ok(())
+                }
+                Some(h) => self.exec_cmd(h.cmd, args),
+            }
+        }
+    }
+
     fn exec_cmd(&mut self, c: Cmd, args: Option<&str>) -> SpotifyResult {
         use Cmd::*;
         match c {
@@ -96,19 +157,29 @@ impl Controller {
             SearchArtist => self.search_artist(args),
             SearchPlaylist => self.search_playlist(args),
             SearchAlbum => self.search_album(args),
+            SearchShow => self.search_show(args),
+            SearchEpisode => self.search_episode(args),
 
             // play-first commands
             PlayFirstTrack => self.play_first_track(args),
             PlayFirstAlbum => self.play_first_album(args),
             PlayFirstArtist => self.play_first_artist(args),
             PlayFirstPlaylist => self.play_first_playlist(args),
+            PlayFirstEpisode => self.play_first_episode(args),
 
             // player commands
+            ShowQueue => self.show_queue(),
             SetVolume => self.set_volume(args),
             Shuffle => self.shuffle(args),
             Repeat => self.repeat(args),
             Next => self.next(),
             Prev => self.prev(),
+            Seek => self.seek(args),
+            SeekForward => self.seek(Some(&format!("+{}", args.unwrap_or("10")))),
+            SeekBackward => self.seek(Some(&format!("-{}", args.unwrap_or("10")))),
+            Resume => self.resume(args),
+            Sleep => self.sleep(args),
+            At => self.at(args),
 
             // library commands
             CreatePlaylist => self.create_playlist(args),
@@ -116,11 +187,34 @@ impl Controller {
             DeletePlaylist => self.delete_playlist(args),
             SavePlaying => self.save_playing(args),
             RemovePlaying => self.remove_playing(args),
+            CopyTracks => self.copy_tracks(args),
+            MoveTracks => self.move_tracks(args),
+            Where => self.where_track(args),
+            Smart => self.smart(args),
+            Liked => self.liked(args),
+            LikePlaying => self.like_playing(),
+            UnlikePlaying => self.unlike_playing(),
+            SavedAlbums => self.saved_albums(args),
+            FollowedArtists => self.followed_artists(),
+            SavedShows => self.saved_shows(),
+            SortPlaylist => self.sort_playlist(args),
+            ShufflePlaylist => self.shuffle_playlist(args),
+            Merge => self.playlist_set_op(SetOp::Merge, args),
+            Intersect => self.playlist_set_op(SetOp::Intersect, args),
+            Subtract => self.playlist_set_op(SetOp::Subtract, args),
+
+            // discovery commands
+            Recommend => self.recommend(args),
+            Features => self.features(args),
+            History => self.history(args),
+            Top => self.top(args),
+            Stats => self.stats(args),
 
             // misc commands
             Help => self.show_help(args),
             PlayUserPlaylist => self.play_user_playlist(args),
             Show => self.show(args),
+            Watch => self.watch(args),
             SetDevice => self.set_device(args),
             Prompt => self.set_prompt(args),
         }
@@ -131,6 +225,86 @@ impl Controller {
             h.show_usage();
         }
     }
+
+    /// The running task with the given name, finished tasks are dropped first.
+    fn task(&mut self, name: &str) -> Option<&Task> {
+        self.reap_tasks();
+        self.tasks.iter().find(|t| t.name == name)
+    }
+
+    /// Prints what the background tasks reported since the last prompt.
+    fn print_reports(&self) {
+        for t in &self.tasks {
+            for r in t.take_reports() {
+                println!("{}", r);
+            }
+        }
+    }
+
+    /// Drops the finished tasks, printing what they reported last.
+    fn reap_tasks(&mut self) {
+        self.tasks.retain(|t| {
+            if !t.is_done() {
+                return true;
+            }
+            for r in t.take_reports() {
+                println!("{}", r);
+            }
+            false
+        });
+    }
+
+    /// Starts a task, replacing the one with the same name.
+    /// Returns `true` if there was one.
+    fn start_task(&mut self, task: Task) -> bool {
+        let replaced = self.cancel_task(&task.name);
+        self.tasks.push(task);
+        replaced
+    }
+
+    /// Follows the playback in the background for the hooks and the listening log, if they're configured.
+    fn start_watcher(&mut self) {
+        if self.config.hooks.is_empty() && self.config.listening_log.is_none() {
+            return;
+        }
+        let session = self.session.clone();
+        let hooks = self.config.hooks.clone();
+        let log = self.config.listening_log.clone();
+        self.start_task(Task::spawn("watcher", None, move |stop| {
+            watcher::watch(&session, watcher::INTERVAL, &stop, |event, old, now| {
+                if let Some(log) = log.as_ref() {
+                    if let Some(l) = log.listen(event, old, now) {
+                        if let Err(e) = listens::append(&log.path, &l) {
+                            stop.report(format!("listening log error: {}", e));
+                        }
+                    }
+                }
+                for h in hooks.iter().filter(|h| h.wants(event)) {
+                    if let Err(e) = h.fire(event, now) {
+                        stop.report(format!("hook error: {}", e));
+                    }
+                }
+            });
+        }));
+    }
+
+    /// Replaces the client with one whose token won't expire in the next few minutes.
+    fn refresh_client(&mut self) -> SpotifyResult {
+        self.client = self.session.client()?;
+        Ok(())
+    }
+
+    /// Cancels the task with the given name, returns `false` if it wasn't running.
+    fn cancel_task(&mut self, name: &str) -> bool {
+        self.reap_tasks();
+        match self.tasks.iter().position(|t| t.name == name) {
+            Some(i) => {
+                self.tasks.remove(i).cancel();
+                true
+            }
+            None => false,
+        }
+    }
 }
 
 // library commands
@@ -240,15 +414,21 @@ impl Controller {
     }
 
     fn save_playing(&mut self, arg: Option<&str>) -> SpotifyResult {
-        let track = match self.playing_track()? {
+        let item = self
+            .client
+            .current_playing(
+                None,
+                Some(vec![AdditionalType::Track, AdditionalType::Episode]),
+            )?
+            .and_then(|x| x.item);
+        match item {
             None => {
                 println!("not playing anything");
-                return Ok(());
+                Ok(())
             }
-            Some(t) => t,
-        };
-
-        self.save_track(track, arg)
+            Some(PlayingItem::Track(t)) => self.save_track(t, arg),
+            Some(PlayingItem::Episode(e)) => self.save_episode(&e.id, &e.name),
+        }
     }
 
     fn remove_playing(&mut self, arg: Option<&str>) -> SpotifyResult {
@@ -260,6 +440,10 @@ impl Controller {
             }
         };
 
+        if arg == Some("--all") {
+            return self.remove_everywhere(&track);
+        }
+
         let mut pl = match self.choose_user_playlist(arg)? {
             Some(p) => p,
             None => {
@@ -282,12 +466,7 @@ impl Controller {
             Playlist::Full(mut p) => {
                 // remove track from playlist, if it's there
                 p.tracks.items.retain(|pl_track| {
-                    if pl_track
-                        .track
-                        .as_ref()
-                        .map(|t| t.id.eq(&track.id) || t.uri.eq(&track.uri))
-                        .unwrap_or(false)
-                    {
+                    if playlist::is_track(pl_track, &track) {
                         // playlist contains the track
                         contains = true;
                         false
@@ -329,6 +508,205 @@ impl Controller {
                 }
             })
     }
+
+    fn liked(&mut self, arg: Option<&str>) -> SpotifyResult {
+        match arg {
+            None => (),
+            Some(a) if a.eq_ignore_ascii_case("play") => {
+                let liked = self.liked_uri();
+                return self
+                    .on_device(|c, dev| {
+                        c.start_playback(dev, Some(liked.clone()), None, None, None)
+                    })
+                    .map(|_| {
+                        self.playing = true;
+                        println!("playing your liked songs");
+                    });
+            }
+            Some(_) => {
+                self.show_usage(Cmd::Liked);
+                return Ok(());
+            }
+        }
+
+        let page = self.client.current_user_saved_tracks(50, 0)?;
+        if page.items.is_empty() {
+            println!("you don't have any liked songs");
+            return Ok(());
+        }
+        println!("you have {} liked songs", page.total);
+
+        let mut offset = page.items.len() as u32;
+        let next_page: search_cmd::NextPage<Noted> = Box::new(move |client| {
+            let page = client.current_user_saved_tracks(50, offset)?;
+            offset += page.items.len() as u32;
+            Ok(page.items.into_iter().map(liked_entry).collect())
+        });
+
+        self.track_list_shell(TrackList {
+            context: Some(self.liked_uri()),
+            next_page: if page.next.is_some() {
+                Some(next_page)
+            } else {
+                None
+            },
+            ..TrackList::from(page.items.into_iter().map(liked_entry).collect::<Vec<_>>())
+        })
+    }
+
+    fn like_playing(&mut self) -> SpotifyResult {
+        match self.playing_track()? {
+            Some(t) => self.like_track(&t),
+            None => {
+                println!("not playing anything");
+                Ok(())
+            }
+        }
+    }
+
+    fn unlike_playing(&mut self) -> SpotifyResult {
+        match self.playing_track()? {
+            Some(t) => self.unlike_track(&t),
+            None => {
+                println!("not playing anything");
+                Ok(())
+            }
+        }
+    }
+
+    fn sort_playlist(&mut self, arg: Option<&str>) -> SpotifyResult {
+        let arg = match arg {
+            Some(a) => a,
+            None => {
+                self.show_usage(Cmd::SortPlaylist);
+                return Ok(());
+            }
+        };
+
+        let (name, spec) = match arg.rfind(" by ") {
+            Some(i) => (Some(arg[..i].trim()), &arg[i + 4..]),
+            None if arg.starts_with("by ") => (None, &arg[3..]),
+            None => {
+                self.show_usage(Cmd::SortPlaylist);
+                return Ok(());
+            }
+        };
+
+        let mut spec = spec.split_whitespace();
+        let key = match spec.next().map(str::parse::<SortKey>) {
+            Some(Ok(k)) => k,
+            Some(Err(e)) => {
+                println!("{}", e);
+                return Ok(());
+            }
+            None => {
+                self.show_usage(Cmd::SortPlaylist);
+                return Ok(());
+            }
+        };
+        let desc = match spec.next().map(|s| s.to_lowercase()).as_deref() {
+            None | Some("asc") => false,
+            Some("desc") => true,
+            Some(_) => {
+                self.show_usage(Cmd::SortPlaylist);
+                return Ok(());
+            }
+        };
+
+        let pl = match self.choose_user_playlist(name)? {
+            Some(p) => p,
+            None => {
+                println!("cancelled");
+                return Ok(());
+            }
+        };
+
+        self.rewrite_order(pl, |items| {
+            let mut order = (0..items.len()).collect::<Vec<_>>();
+            order.sort_by(|&a, &b| key.compare(&items[a], &items[b], desc));
+            order
+        })
+    }
+
+    fn shuffle_playlist(&mut self, arg: Option<&str>) -> SpotifyResult {
+        let pl = match self.choose_user_playlist(arg)? {
+            Some(p) => p,
+            None => {
+                println!("cancelled");
+                return Ok(());
+            }
+        };
+
+        self.rewrite_order(pl, |items| {
+            let mut order = (0..items.len()).collect::<Vec<_>>();
+            order.shuffle(&mut rand::thread_rng());
+            order
+        })
+    }
+
+    /// `arrange` returns the current indices of the items in their new order.
+    fn rewrite_order<F>(&mut self, pl: Playlist, arrange: F) -> SpotifyResult
+    where
+        F: FnOnce(&[PlaylistTrack]) -> Vec<usize>,
+    {
+        if pl.owner().id != self.user {
+            println!("{} is not your playlist, can't reorder it", pl.name());
+            return Ok(());
+        }
+
+        let pl = self.fetch_playlist(pl.id())?;
+        let items = pl.all_tracks(&self.client, &self.user)?;
+        let mut order = vec![0; items.len()];
+        for (target, current) in arrange(&items).into_iter().enumerate() {
+            order[current] = target;
+        }
+
+        let moves = playlist::reorder_moves(&order);
+        if moves.is_empty() {
+            println!("{} is already in that order", pl.name());
+            return Ok(());
+        }
+        if !read_bool(&format!(
+            "reorder {} ({} of {} tracks will move)?",
+            pl.name(),
+            moves.len(),
+            items.len()
+        )) {
+            println!("cancelled");
+            return Ok(());
+        }
+
+        let mut snapshot = self.fetch_playlist(pl.id())?.snapshot_id().to_string();
+        if snapshot != pl.snapshot_id() {
+            println!("{} was modified since it was read, try again", pl.name());
+            return Ok(());
+        }
+
+        for (from, to) in moves.iter().copied() {
+            snapshot = self
+                .client
+                .user_playlist_recorder_tracks(
+                    &self.user,
+                    pl.id(),
+                    from as i32,
+                    1,
+                    to as i32,
+                    Some(snapshot),
+                )?
+                .snapshot_id;
+        }
+
+        let pl = self.fetch_playlist(pl.id())?;
+        if pl.snapshot_id() != snapshot {
+            println!(
+                "warning: {} was modified by someone else while it was being reordered",
+                pl.name()
+            );
+        }
+        println!("reordered {} in {} moves", pl.name(), moves.len());
+        self.replace_cached(pl);
+        Ok(())
+    }
 }
 
 // play-first commands
@@ -421,7 +799,7 @@ impl Controller {
 
 // player commands
 impl Controller {
-    fn set_volume(&self, arg: Option<&str>) -> SpotifyResult {
+    fn set_volume(&mut self, arg: Option<&str>) -> SpotifyResult {
         let n = match arg {
             Some(s) => match s.parse::<u8>() {
                 Ok(n) => n,
@@ -438,7 +816,7 @@ impl Controller {
 
         let n = if n > 100 { 100_u8 } else { n };
 
-        self.client.volume(n, self.device.clone())
+        self.on_device(|c, dev| c.volume(n, dev))
     }
 
     fn change_volume(&self, mut n: i32) -> SpotifyResult {
@@ -473,7 +851,7 @@ impl Controller {
             })
     }
 
-    fn shuffle(&self, arg: Option<&str>) -> SpotifyResult {
+    fn shuffle(&mut self, arg: Option<&str>) -> SpotifyResult {
         let sh = match arg {
             None => None,
             Some(s) => Some(match &s.to_lowercase()[..] {
@@ -495,14 +873,13 @@ impl Controller {
                     Ok(())
                 }
                 _ => self
-                    .client
-                    .shuffle(!cont.shuffle_state, self.device.clone())
+                    .on_device(|c, dev| c.shuffle(!cont.shuffle_state, dev))
                     .map(|_| {
                         println!("shuffle = {}", !cont.shuffle_state);
                     }),
             }
         } else if let Some(b) = sh {
-            self.client.shuffle(b, self.device.clone()).map(|_| {
+            self.on_device(|c, dev| c.shuffle(b, dev)).map(|_| {
                 println!("shuffle = {}", b);
             })
         } else {
@@ -511,7 +888,7 @@ impl Controller {
         }
     }
 
-    fn repeat(&self, arg: Option<&str>) -> SpotifyResult {
+    fn repeat(&mut self, arg: Option<&str>) -> SpotifyResult {
         let rep = match arg {
             Some(s) => match &s.to_lowercase()[..] {
                 "off" | "false" | "no" => RepeatState::Off,
@@ -528,7 +905,7 @@ impl Controller {
             }
         };
 
-        self.client.repeat(rep, self.device.clone()).map(|_| {
+        self.on_device(|c, dev| c.repeat(rep, dev)).map(|_| {
             println!("repeat = {}", rep.as_str());
         })
     }
@@ -536,21 +913,20 @@ impl Controller {
     fn toggle(&mut self) -> SpotifyResult {
         self.playing = !self.playing;
         if self.playing {
-            self.client
-                .start_playback(self.device.clone(), None, None, None, None)
+            self.on_device(|c, dev| c.start_playback(dev, None, None, None, None))
         } else {
-            self.client.pause_playback(self.device.clone())
+            self.on_device(|c, dev| c.pause_playback(dev))
         }
     }
 
     fn prev(&mut self) -> SpotifyResult {
-        self.client.previous_track(None).map(|_| {
+        self.on_device(|c, dev| c.previous_track(dev)).map(|_| {
             self.playing = true;
         })
     }
 
     fn next(&mut self) -> SpotifyResult {
-        self.client.next_track(None).map(|_| {
+        self.on_device(|c, dev| c.next_track(dev)).map(|_| {
             self.playing = true;
         })
     }
@@ -589,44 +965,123 @@ impl Controller {
     }
 
     fn set_device(&mut self, arg: Option<&str>) -> SpotifyResult {
-        if let Some(dev) = self.choose_device(arg)? {
-            self.client.transfer_playback(&dev.id, false).map(|_| {
-                println!("playing on {}", &dev.name);
-                self.device = Some(dev.id);
-            })
+        let mut prefer = false;
+        let mut words = Vec::new();
+        for w in arg.unwrap_or_default().split_whitespace() {
+            match w {
+                "--prefer" => prefer = true,
+                "--forget" => {
+                    let mut state = State::load(&self.state_path)?;
+                    return match state.preferred_device.take() {
+                        Some(name) => state.save(&self.state_path).map(|_| {
+                            println!("{} is no longer your preferred device", name);
+                        }),
+                        None => {
+                            println!("you have no preferred device");
+                            Ok(())
+                        }
+                    };
+                }
+                _ => words.push(w),
+            }
+        }
+        let name = words.join(" ");
+        let name = if name.is_empty() {
+            None
         } else {
-            println!("cancelled");
-            Ok(())
+            Some(&name[..])
+        };
+
+        let dev = match self.choose_device(name)? {
+            Some(d) => d,
+            None => {
+                println!("cancelled");
+                return Ok(());
+            }
+        };
+        self.client.transfer_playback(&dev.id, false)?;
+        println!("playing on {}", &dev.name);
+        self.device = Some(dev.id);
+        if prefer {
+            let mut state = State::load(&self.state_path)?;
+            state.preferred_device = Some(dev.name.clone());
+            state.save(&self.state_path)?;
+            println!("{} is now your preferred device", &dev.name);
         }
+        Ok(())
+    }
+
+    /// Sends a player request to the selected device.
+    /// If it fails because no device is active, a device is activated and only this request is sent again.
+    fn on_device<T, F>(&mut self, f: F) -> Result<T, failure::Error>
+    where
+        F: Fn(&Spotify, Option<String>) -> Result<T, failure::Error>,
+    {
+        match f(&self.client, self.device.clone()) {
+            Err(e) if api::is_no_active_device(&e) => {
+                if self.activate_device()? {
+                    f(&self.client, self.device.clone())
+                } else {
+                    Err(e)
+                }
+            }
+            res => res,
+        }
+    }
+
+    /// Moves the playback to the preferred device, or one you choose, so a request that failed for the lack
+    /// of an active device can be sent again. Returns `false` if no device was chosen.
+    fn activate_device(&mut self) -> Result<bool, failure::Error> {
+        let preferred = State::load(&self.state_path)?.preferred_device;
+        let mut devs = self.client.device()?.devices;
+        let dev =
+            match preferred.and_then(|p| devs.iter().position(|d| crate::equalfold(&d.name, &p))) {
+                Some(i) => devs.remove(i),
+                None if devs.len() == 1 => devs.remove(0),
+                None if !crate::is_interactive() => {
+                    return Err(failure::format_err!(
+                        "no device is active and there is no preferred device to activate"
+                    ));
+                }
+                None => {
+                    println!("there is no active device");
+                    match self.choose_device(None)? {
+                        Some(d) => d,
+                        None => return Ok(false),
+                    }
+                }
+            };
+
+        self.client.transfer_playback(&dev.id, false)?;
+        println!("activated {}", &dev.name);
+        self.device = Some(dev.id);
+        // the device takes a moment to become active
+        thread::sleep(Duration::from_millis(500));
+        Ok(true)
     }
 
     fn show(&mut self, arg: Option<&str>) -> SpotifyResult {
         if let Some(a) = arg {
             match a {
-                "playing" | "track" => self.show_playing(),
+                "playing" | "track" => self.now_playing(),
                 "lib" | "pl" => self.show_user_playlists(),
                 _ => self.show_user_playlist(a),
             }
         } else {
-            self.show_playback()
+            self.now_playing()
         }
     }
 
     fn play_user_playlist(&mut self, arg: Option<&str>) -> SpotifyResult {
         if let Some(pl) = self.choose_user_playlist(arg)? {
-            self.client
-                .start_playback(
-                    self.device.clone(),
-                    Some(pl.uri().to_string()),
-                    None,
-                    None,
-                    None,
-                )
-                .map(|_| {
-                    println!("playing {}", pl.name());
-                    self.playing = true;
-                    self.last_pl = Some(pl);
-                })
+            self.on_device(|c, dev| {
+                c.start_playback(dev, Some(pl.uri().to_string()), None, None, None)
+            })
+            .map(|_| {
+                println!("playing {}", pl.name());
+                self.playing = true;
+                self.last_pl = Some(pl);
+            })
         } else {
             println!("cancelled");
             Ok(())
@@ -655,7 +1110,7 @@ impl Controller {
             for (i, p) in pls.iter().enumerate() {
                 println!("#{no:2} | {name}", no = i, name = p.name());
             }
-            read_number(0, pls.len()).map(|n| pls.remove(n))
+            read_number(0, pls.len() - 1).map(|n| pls.remove(n))
         })
     }
 
@@ -671,79 +1126,223 @@ impl Controller {
         })
     }
 
+    /// Finds a device by its name or alias, ignoring the case; a unique prefix of the name is also accepted.
+    /// Without a name, or if the name is ambiguous, prompts you to pick one.
     fn choose_device(&self, arg: Option<&str>) -> Result<Option<Device>, failure::Error> {
         let mut devs = self.client.device()?.devices;
-        Ok(if let Some(name) = arg {
-            devs.into_iter().find(|d| d.name.eq(name))
-        } else if devs.is_empty() {
+        if devs.is_empty() {
             println!("did not detect any device");
-            None
+            return Ok(None);
+        }
+
+        if let Some(name) = arg {
+            let name = self.device_alias(name);
+            self.match_devices(&mut devs, name);
+            match devs.len() {
+                0 => {
+                    println!("there is no device named {}", name);
+                    return Ok(None);
+                }
+                1 => return Ok(devs.pop()),
+                _ => println!("more than one device matches {}", name),
+            }
+        }
+
+        let preferred = State::load(&self.state_path)?.preferred_device;
+        for (i, d) in devs.iter().enumerate() {
+            let mut marks = Vec::new();
+            if d.is_active {
+                marks.push("active");
+            }
+            if preferred
+                .as_ref()
+                .map_or(false, |p| crate::equalfold(p, &d.name))
+            {
+                marks.push("preferred");
+            }
+            println!(
+                "#{no:2} | {name} ({kind}, {volume}%){marks}",
+                no = i,
+                name = &d.name,
+                kind = format!("{:?}", d._type).to_lowercase(),
+                volume = d.volume_percent,
+                marks = if marks.is_empty() {
+                    String::new()
+                } else {
+                    format!(" [{}]", marks.join(", "))
+                }
+            );
+        }
+        Ok(read_number(0, devs.len() - 1).map(|n| devs.remove(n)))
+    }
+
+    /// The device name an alias from the config stands for.
+    fn device_alias<'a>(&'a self, name: &'a str) -> &'a str {
+        self.config
+            .device_aliases
+            .iter()
+            .find(|(alias, _)| crate::equalfold(alias, name))
+            .map_or(name, |(_, dev)| &dev[..])
+    }
+
+    /// Keeps the devices named `name`, or if there are none the ones whose name starts with it.
+    fn match_devices(&self, devs: &mut Vec<Device>, name: &str) {
+        let lower = name.to_lowercase();
+        let matches = |d: &Device| crate::equalfold(&d.name, name);
+        let prefixed = |d: &Device| d.name.to_lowercase().starts_with(&lower);
+        if devs.iter().any(matches) {
+            devs.retain(matches);
         } else {
-            for (i, d) in devs.iter().enumerate() {
-                println!("# {} : {}", i, &d.name);
+            devs.retain(prefixed);
+        }
+    }
+
+    /// Finds the one device matching `name` without asking, for when nobody is at the prompt.
+    fn find_device(&self, name: &str) -> Result<Device, failure::Error> {
+        let name = self.device_alias(name);
+        let mut devs = self.client.device()?.devices;
+        self.match_devices(&mut devs, name);
+        match devs.len() {
+            0 => Err(failure::format_err!("there is no device named {}", name)),
+            1 => Ok(devs.remove(0)),
+            _ => Err(failure::format_err!(
+                "more than one device matches {}: {}",
+                name,
+                devs.iter()
+                    .map(|d| &d.name[..])
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        }
+    }
+
+    /// Fetches audio features in batches, keyed by track id.
+    fn audio_features(
+        &self,
+        ids: &[String],
+    ) -> Result<HashMap<String, AudioFeatures>, failure::Error> {
+        let mut features = HashMap::new();
+        for chunk in ids.chunks(100) {
+            if let Some(payload) = self.client.audios_features(chunk)? {
+                features.extend(
+                    payload
+                        .audio_features
+                        .into_iter()
+                        .map(|f| (f.id.clone(), f)),
+                );
             }
-            read_number(0, devs.len()).map(|n| devs.remove(n))
-        })
+        }
+        Ok(features)
+    }
+
+    /// The uri of the "liked songs" collection, it can be played like a playlist.
+    fn liked_uri(&self) -> String {
+        format!("spotify:user:{}:collection", &self.user)
+    }
+
+    fn simplify_album(a: FullAlbum) -> SimplifiedAlbum {
+        SimplifiedAlbum {
+            album_group: None,
+            album_type: Some(a.album_type.as_str().to_string()),
+            artists: a.artists,
+            available_markets: a.available_markets,
+            external_urls: a.external_urls,
+            href: Some(a.href),
+            id: Some(a.id),
+            images: a.images,
+            name: a.name,
+            release_date: Some(a.release_date),
+            release_date_precision: Some(a.release_date_precision),
+            restrictions: None,
+            _type: a._type,
+            uri: Some(a.uri),
+        }
+    }
+
+    fn fetch_playlist(&self, id: &str) -> Result<Playlist, failure::Error> {
+        let mut id = id.to_string();
+        self.client
+            .user_playlist(&self.user, Some(id.as_mut_str()), None, None)
+            .map(Playlist::from)
+    }
+
+    fn replace_cached(&mut self, pl: Playlist) {
+        if let Some(v) = self.pl_cache.as_mut() {
+            if let Some(p) = v.iter_mut().find(|p| p.id() == pl.id()) {
+                *p = pl;
+            }
+        }
     }
 
     fn get_playlists(&mut self) -> Result<Vec<Playlist>, failure::Error> {
         if let Some(cache) = self.pl_cache.as_ref() {
             Ok(cache.to_vec())
         } else {
-            let pls = self
-                .client
-                .current_user_playlists(Some(50), None)
-                .map(|p| p.items.into_iter().map(Playlist::from).collect::<Vec<_>>())?;
+            let pls = self.fetch_playlists()?;
             self.pl_cache = Some(pls.clone());
             Ok(pls)
         }
     }
+
+    /// Fetches all of your playlists, 50 at a time.
+    fn fetch_playlists(&self) -> Result<Vec<Playlist>, failure::Error> {
+        let mut pls = Vec::new();
+        loop {
+            let page = self
+                .client
+                .current_user_playlists(Some(50), Some(pls.len() as u32))?;
+            let done = page.next.is_none() || page.items.is_empty();
+            pls.extend(page.items.into_iter().map(Playlist::from));
+            if done {
+                return Ok(pls);
+            }
+        }
+    }
 }
 
 impl Controller {
     fn play_track(&mut self, track: &FullTrack) -> SpotifyResult {
-        self.client
-            .start_playback(
-                self.device.clone(),
-                None,
-                Some(vec![track.uri.clone()]),
-                None,
-                None,
-            )
-            .map(|_| {
-                self.playing = true;
-                println!(
-                    "playing {} [{}] by {}",
-                    &track.name,
-                    &track.album.name,
-                    crate::join_artists(&track.artists)
-                );
-            })
+        self.on_device(|c, dev| {
+            c.start_playback(dev, None, Some(vec![track.uri.clone()]), None, None)
+        })
+        .map(|_| {
+            self.playing = true;
+            println!(
+                "playing {} [{}] by {}",
+                &track.name,
+                &track.album.name,
+                crate::join_artists(&track.artists)
+            );
+        })
     }
 
     fn play_album(&mut self, alb: &SimplifiedAlbum) -> SpotifyResult {
         if let Some(uri) = alb.uri.as_ref() {
-            self.client.start_playback(
-                self.device.clone(),
-                Some(uri.clone()),
-                None,
-                Some(Offset {
-                    position: Some(0),
-                    uri: None,
-                }),
-                None,
-            )
+            self.on_device(|c, dev| {
+                c.start_playback(
+                    dev,
+                    Some(uri.clone()),
+                    None,
+                    Some(Offset {
+                        position: Some(0),
+                        uri: None,
+                    }),
+                    None,
+                )
+            })
         } else if let Some(id) = alb.id.as_ref() {
-            self.client.start_playback(
-                self.device.clone(),
-                Some(id.clone()),
-                None,
-                Some(Offset {
-                    position: Some(0),
-                    uri: None,
-                }),
-                None,
-            )
+            self.on_device(|c, dev| {
+                c.start_playback(
+                    dev,
+                    Some(id.clone()),
+                    None,
+                    Some(Offset {
+                        position: Some(0),
+                        uri: None,
+                    }),
+                    None,
+                )
+            })
         } else {
             // can't do anything here, uri and id unavailable
             println!("error: the track uri and id can't be found");
@@ -760,18 +1359,33 @@ impl Controller {
     }
 
     fn play_artist(&mut self, art: &FullArtist) -> SpotifyResult {
-        self.client
-            .start_playback(self.device.clone(), Some(art.uri.clone()), None, None, None)
+        self.on_device(|c, dev| c.start_playback(dev, Some(art.uri.clone()), None, None, None))
             .map(|_| {
                 self.playing = true;
                 println!("playing {}", &art.name);
             })
     }
 
+    fn play_playlist_from(&mut self, pl: &Playlist, position: u32) -> SpotifyResult {
+        self.on_device(|c, dev| {
+            c.start_playback(
+                dev,
+                Some(pl.uri().to_string()),
+                None,
+                offset::for_position(position),
+                None,
+            )
+        })
+        .map(|_| {
+            self.playing = true;
+            println!("playing {} from track {}", &pl.name(), position);
+        })
+    }
+
     fn play_playlist(&mut self, pl: &Playlist) -> SpotifyResult {
-        self.client
-            .start_playback(
-                self.device.clone(),
+        self.on_device(|c, dev| {
+            c.start_playback(
+                dev,
                 Some(pl.uri().to_string()),
                 None,
                 Some(Offset {
@@ -780,70 +1394,15 @@ impl Controller {
                 }),
                 None,
             )
-            .map(|_| {
-                self.playing = true;
-                println!("playing {}", &pl.name());
-            })
+        })
+        .map(|_| {
+            self.playing = true;
+            println!("playing {}", &pl.name());
+        })
     }
 }
 
 impl Controller {
-    fn show_playing(&mut self) -> SpotifyResult {
-        if let Some(playing) = self.client.current_playing(
-            None,
-            Some(vec![AdditionalType::Track, AdditionalType::Episode]),
-        )? {
-            self.playing = playing.is_playing;
-            match playing.item.as_ref() {
-                Some(PlayingItem::Track(t)) => {
-                    println!("{} by {}", &t.name, crate::join_artists(&t.artists));
-                    println!("playing = {}", &playing.is_playing);
-                }
-                Some(PlayingItem::Episode(e)) => {
-                    println!(
-                        "{} from {} by {}\n{}",
-                        &e.name, &e.show.name, &e.show.publisher, &e.description
-                    );
-                    println!("playing = {}", &playing.is_playing);
-                }
-                None => {
-                    println!("not playing anything");
-                }
-            };
-        } else {
-            println!("not playing anything");
-        }
-        Ok(())
-    }
-
-    fn show_playback(&mut self) -> SpotifyResult {
-        if let Some(playing) = self.client.current_playback(
-            None,
-            Some(vec![AdditionalType::Track, AdditionalType::Episode]),
-        )? {
-            self.playing = playing.is_playing;
-            println!("device  | {}", &playing.device.name);
-            println!("repeat  | {}", playing.repeat_state.as_str());
-            println!("shuffle | {}", playing.shuffle_state);
-            println!("playing | {}", playing.is_playing);
-            match &playing.item.as_ref() {
-                Some(PlayingItem::Track(t)) => {
-                    println!("{} by {}", &t.name, crate::join_artists(&t.artists));
-                }
-                Some(PlayingItem::Episode(e)) => {
-                    println!(
-                        "{} from {} by {}\n{}",
-                        &e.name, &e.show.name, &e.show.publisher, &e.description
-                    );
-                }
-                None => {}
-            };
-        } else {
-            println!("not playing anything");
-        }
-        Ok(())
-    }
-
     fn show_user_playlist(&self, _name: &str) -> SpotifyResult {
         println!("unimplemented");
         Ok(())
@@ -881,13 +1440,12 @@ impl Controller {
 
         let mut dupe = false;
         let pl = if let Playlist::Full(mut p) = pl {
-            if !p.tracks.items.iter().any(|playlist_track| {
-                playlist_track
-                    .track
-                    .as_ref()
-                    .map(|t| t.id.eq(&track.id) || t.uri.eq(&track.uri))
-                    .unwrap_or(false)
-            }) {
+            if !p
+                .tracks
+                .items
+                .iter()
+                .any(|playlist_track| playlist::is_track(playlist_track, &track))
+            {
                 // no dupes, add the track
                 p.tracks.items.insert(
                     0,
@@ -935,8 +1493,9 @@ impl Controller {
             })
     }
 
-    fn queue(&self, uri: String) -> SpotifyResult {
-        self.client.add_item_to_queue(uri, self.device.clone())
+    fn queue(&mut self, uri: String) -> SpotifyResult {
+        self.on_device(|c, dev| c.add_item_to_queue(uri.clone(), dev))
+            .map(|_| self.queued.borrow_mut().push(uri))
     }
 
     fn like_track(&self, t: &FullTrack) -> SpotifyResult {
@@ -958,6 +1517,27 @@ impl Controller {
         }
     }
 
+    fn unlike_track(&self, t: &FullTrack) -> SpotifyResult {
+        let id = match t.id.as_ref() {
+            Some(i) => i.clone(),
+            None => t.uri.clone(),
+        };
+        if let Ok(Some(false)) = self
+            .client
+            .current_user_saved_tracks_contains(&[id.clone()])
+            .map(|v| v.first().copied())
+        {
+            println!("{} is not in your favourites folder", &t.name);
+            Ok(())
+        } else {
+            self.client
+                .current_user_saved_tracks_delete(&[id])
+                .map(|_| {
+                    println!("removed {} from your favourites folder", &t.name);
+                })
+        }
+    }
+
     fn follow_artist(&self, art: &FullArtist) -> SpotifyResult {
         let id = art.id.clone();
 
@@ -975,6 +1555,23 @@ impl Controller {
         }
     }
 
+    fn unfollow_artist(&self, art: &FullArtist) -> SpotifyResult {
+        let id = art.id.clone();
+
+        if let Ok(Some(false)) = self
+            .client
+            .user_artist_check_follow(&[id.clone()])
+            .map(|v| v.first().copied())
+        {
+            println!("you are not following {}", &art.name);
+            Ok(())
+        } else {
+            self.client.user_unfollow_artists(&[id]).map(|_| {
+                println!("unfollowed {}", &art.name);
+            })
+        }
+    }
+
     fn save_album(&self, alb: &SimplifiedAlbum) -> SpotifyResult {
         let id = match alb.id.as_ref() {
             Some(i) => i.clone(),
@@ -998,6 +1595,82 @@ impl Controller {
         }
     }
 
+    fn unsave_album(&self, alb: &SimplifiedAlbum) -> SpotifyResult {
+        let id = match alb.id.as_ref() {
+            Some(i) => i.clone(),
+            None => {
+                println!("the album is missing an ID, can't remove it from the library");
+                return Ok(());
+            }
+        };
+
+        if let Ok(Some(false)) = self
+            .client
+            .current_user_saved_albums_contains(&[id.clone()])
+            .map(|v| v.first().copied())
+        {
+            println!("{} is not in your library", &alb.name);
+            Ok(())
+        } else {
+            self.client
+                .current_user_saved_albums_delete(&[id])
+                .map(|_| {
+                    println!("removed {} from your library", &alb.name);
+                })
+        }
+    }
+
+    /// Fetches every track of an album.
+    fn album_tracks(&self, alb: &SimplifiedAlbum) -> Result<Vec<SimplifiedTrack>, failure::Error> {
+        let id = match alb.id.as_ref().or(alb.uri.as_ref()) {
+            Some(id) => id,
+            None => return Ok(Vec::new()),
+        };
+
+        let mut tracks = Vec::new();
+        loop {
+            let page = self.client.album_track(id, 50, tracks.len() as u32)?;
+            let done = page.next.is_none() || page.items.is_empty();
+            tracks.extend(page.items);
+            if done {
+                return Ok(tracks);
+            }
+        }
+    }
+
+    fn unfollow_playlist(&mut self, pl: &Playlist) -> SpotifyResult {
+        if pl.owner().id == self.user {
+            if !read_bool(&format!(
+                "{} is your playlist, unfollowing it deletes it; continue?",
+                pl.name()
+            )) {
+                println!("cancelled");
+                return Ok(());
+            }
+        } else if let Ok(Some(false)) = self
+            .client
+            .user_playlist_check_follow(&pl.owner().id, pl.id(), &[self.user.clone()])
+            .map(|v| v.first().copied())
+        {
+            println!("you are not following {}", pl.name());
+            return Ok(());
+        }
+
+        self.client
+            .user_playlist_unfollow(&self.user, pl.id())
+            .map(|_| {
+                if let Some(s) = self.last_pl.as_ref() {
+                    if s.id().eq(pl.id()) {
+                        self.last_pl = None;
+                    }
+                }
+                if let Some(v) = self.pl_cache.as_mut() {
+                    v.retain(|p| p.id() != pl.id());
+                }
+                println!("unfollowed {}", pl.name());
+            })
+    }
+
     fn follow_playlist(&self, pl: &Playlist) -> SpotifyResult {
         let owner_id = &pl.owner().id;
         let pl_id = pl.id();
@@ -1017,3 +1690,8 @@ impl Controller {
         }
     }
 }
+
+fn liked_entry(s: SavedTrack) -> Noted {
+    let added = format!("liked {}", s.added_at.format("%Y-%m-%d"));
+    (s.track, Some(added))
+}
@@ -0,0 +1,532 @@
+use super::{
+    search_cmd::{NextPage, Noted},
+    Controller, TrackList,
+};
+use crate::{
+    api,
+    command::Cmd,
+    listens::{self, Count},
+    search,
+    smart::Condition,
+    SpotifyResult,
+};
+use chrono::{Duration, Local};
+use rspotify::{
+    model::{playing::PlayHistory, track::FullTrack},
+    senum::TimeRange,
+};
+use serde_json::{Map, Number, Value};
+use std::{collections::HashMap, convert::TryFrom};
+
+const KEYS: &[&str] = &[
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+/// How many rows each section of `stats` shows.
+const STATS_ROWS: usize = 10;
+
+/// The attributes the recommendations can be tuned with.
+const TUNABLE: &[&str] = &[
+    "acousticness",
+    "danceability",
+    "duration_ms",
+    "energy",
+    "instrumentalness",
+    "key",
+    "liveness",
+    "loudness",
+    "mode",
+    "popularity",
+    "speechiness",
+    "tempo",
+    "time_signature",
+    "valence",
+];
+
+#[derive(Default)]
+struct Seeds {
+    artists: Vec<String>,
+    tracks: Vec<String>,
+    genres: Vec<String>,
+    names: Vec<String>,
+}
+
+impl Seeds {
+    fn len(&self) -> usize {
+        self.artists.len() + self.tracks.len() + self.genres.len()
+    }
+
+    fn vec(v: Vec<String>) -> Option<Vec<String>> {
+        if v.is_empty() {
+            None
+        } else {
+            Some(v)
+        }
+    }
+}
+
+impl Controller {
+    pub fn recommend(&mut self, arg: Option<&str>) -> SpotifyResult {
+        let args = crate::split_args(arg.unwrap_or_default());
+        let mut seeds = Seeds::default();
+        let mut payload = Map::new();
+        let mut limit = 20;
+
+        let mut args = args.iter();
+        while let Some(flag) = args.next() {
+            let value = match (flag.strip_prefix("--"), args.next()) {
+                (Some(_), Some(v)) => v,
+                _ => {
+                    self.show_usage(Cmd::Recommend);
+                    return Ok(());
+                }
+            };
+            match &flag[2..] {
+                "seed" => {
+                    if !self.add_seed(&mut seeds, value)? {
+                        return Ok(());
+                    }
+                }
+                "limit" => match value.parse::<u32>() {
+                    Ok(n) if (1..=100).contains(&n) => limit = n,
+                    _ => {
+                        println!("the limit must be a number between 1 and 100");
+                        return Ok(());
+                    }
+                },
+                attr if TUNABLE.contains(&attr) => {
+                    if let Err(e) = tune(&mut payload, attr, value) {
+                        println!("{}", e);
+                        return Ok(());
+                    }
+                }
+                other => {
+                    println!(
+                        "{} is not a known option\navailable attributes are {}",
+                        other,
+                        TUNABLE.join(", ")
+                    );
+                    return Ok(());
+                }
+            }
+        }
+
+        if seeds.len() == 0 && !self.add_seed(&mut seeds, "playing")? {
+            return Ok(());
+        }
+        if seeds.len() > 5 {
+            println!("you can give at most 5 seeds");
+            return Ok(());
+        }
+
+        println!("recommendations based on {}", seeds.names.join(", "));
+        let recs = self.client.recommendations(
+            Seeds::vec(seeds.artists),
+            Seeds::vec(seeds.genres),
+            Seeds::vec(seeds.tracks),
+            limit,
+            None,
+            &payload,
+        )?;
+        if recs.tracks.is_empty() {
+            println!("no recommendations found");
+            return Ok(());
+        }
+
+        let tracks = api::full_tracks(&self.client, &recs.tracks)?;
+        self.track_shell(tracks)
+    }
+
+    pub fn history(&mut self, arg: Option<&str>) -> SpotifyResult {
+        let limit = match arg.map(str::parse::<u32>) {
+            None => 20,
+            Some(Ok(n)) if (1..=50).contains(&n) => n,
+            _ => {
+                println!("please enter a number between 1 and 50");
+                return Ok(());
+            }
+        };
+
+        // playlist names to show where the tracks were played from
+        let names = self
+            .get_playlists()?
+            .iter()
+            .map(|p| (p.uri().to_string(), p.name().to_string()))
+            .collect::<HashMap<_, _>>();
+        let mut before: Option<String> = None;
+        let mut done = false;
+        let mut next_page: NextPage<Noted> = Box::new(move |client| {
+            if done {
+                return Ok(Vec::new());
+            }
+            let page = api::recently_played(client, limit, before.as_deref())?;
+            before = page.cursors.as_ref().and_then(|c| c.before.clone());
+            done = before.is_none() || page.items.is_empty();
+            let simple = page
+                .items
+                .iter()
+                .map(|h| h.track.clone())
+                .collect::<Vec<_>>();
+            let tracks = api::full_tracks(client, &simple)?;
+            Ok(tracks
+                .into_iter()
+                .zip(page.items)
+                .map(|(t, h)| {
+                    let note = played_note(&t, &h, &names);
+                    (t, Some(note))
+                })
+                .collect())
+        });
+
+        let entries = next_page(&self.client)?;
+        if entries.is_empty() {
+            println!("you haven't played anything recently");
+            return Ok(());
+        }
+        self.track_list_shell(TrackList {
+            next_page: Some(next_page),
+            ..TrackList::from(entries)
+        })
+    }
+
+    pub fn top(&mut self, arg: Option<&str>) -> SpotifyResult {
+        let args = crate::split_args(arg.unwrap_or_default());
+        let mut args = args.iter();
+        let artists = match args.next().map(|s| s.to_lowercase()).as_deref() {
+            Some("tracks") | Some("track") => false,
+            Some("artists") | Some("artist") => true,
+            _ => {
+                self.show_usage(Cmd::Top);
+                return Ok(());
+            }
+        };
+
+        let mut range = TimeRange::MediumTerm;
+        let mut limit = 20;
+        let mut save = None;
+        while let Some(flag) = args.next() {
+            match (&flag[..], args.next()) {
+                ("--range", Some(r)) => {
+                    range = match &r.to_lowercase()[..] {
+                        "short" => TimeRange::ShortTerm,
+                        "medium" => TimeRange::MediumTerm,
+                        "long" => TimeRange::LongTerm,
+                        _ => {
+                            println!("the range must be one of short, medium or long");
+                            return Ok(());
+                        }
+                    }
+                }
+                ("--limit", Some(n)) => match n.parse::<u32>() {
+                    Ok(n) if (1..=50).contains(&n) => limit = n,
+                    _ => {
+                        println!("the limit must be a number between 1 and 50");
+                        return Ok(());
+                    }
+                },
+                ("--save", Some(name)) if !artists => save = Some(name.clone()),
+                _ => {
+                    self.show_usage(Cmd::Top);
+                    return Ok(());
+                }
+            }
+        }
+
+        let period = match range {
+            TimeRange::ShortTerm => "the last 4 weeks",
+            TimeRange::MediumTerm => "the last 6 months",
+            TimeRange::LongTerm => "all time",
+        };
+        if artists {
+            let artists = self.client.current_user_top_artists(limit, 0, range)?.items;
+            if artists.is_empty() {
+                println!("spotify doesn't know your top artists yet");
+                return Ok(());
+            }
+            println!("your top {} artists of {}", artists.len(), period);
+            return self.artist_shell(artists);
+        }
+
+        let tracks = self.client.current_user_top_tracks(limit, 0, range)?.items;
+        if tracks.is_empty() {
+            println!("spotify doesn't know your top tracks yet");
+            return Ok(());
+        }
+        if let Some(name) = save {
+            let target = self
+                .get_playlists()?
+                .into_iter()
+                .find(|p| p.owner().id == self.user && p.name_eq(&name));
+            return self.write_playlist(target, &name, tracks, false);
+        }
+
+        println!("your top {} tracks of {}", tracks.len(), period);
+        let entries = tracks
+            .into_iter()
+            .enumerate()
+            .map(|(i, t)| (t, Some(format!("rank {}", i + 1))))
+            .collect::<Vec<_>>();
+        self.track_list_shell(TrackList::from(entries))
+    }
+
+    pub fn stats(&mut self, arg: Option<&str>) -> SpotifyResult {
+        let path = match self.config.listening_log.as_ref() {
+            Some(log) => log.path.clone(),
+            None => {
+                println!("the listening log is not turned on in the config");
+                return Ok(());
+            }
+        };
+        let days = match arg.map(str::parse::<i64>) {
+            None => None,
+            Some(Ok(n)) if n > 0 => Some(n),
+            _ => {
+                self.show_usage(Cmd::Stats);
+                return Ok(());
+            }
+        };
+
+        let mut plays = listens::load(&path)?;
+        if let Some(n) = days {
+            let since = Local::now() - Duration::days(n);
+            plays.retain(|l| l.time >= since);
+        }
+        if plays.is_empty() {
+            println!("there are no plays in the listening log yet");
+            return Ok(());
+        }
+
+        let total = Count {
+            plays: plays.len(),
+            skips: plays.iter().filter(|l| l.skipped).count(),
+        };
+        print_count(&total, "in total");
+
+        println!("\ntop artists:");
+        for (artist, c) in listens::count_by(&plays, |l| l.artists.clone())
+            .iter()
+            .take(STATS_ROWS)
+        {
+            print_count(c, artist);
+        }
+
+        println!("\nplays per day:");
+        let mut per_day = listens::count_by(&plays, |l| Some(l.time.date().naive_local()));
+        per_day.sort_by(|(a, _), (b, _)| b.cmp(a));
+        for (day, c) in per_day.iter().take(STATS_ROWS) {
+            print_count(c, &day.to_string());
+        }
+
+        let per_playlist = listens::count_by(&plays, |l| {
+            l.context
+                .clone()
+                .filter(|c| c.starts_with("spotify:playlist:"))
+        });
+        if per_playlist.is_empty() {
+            return Ok(());
+        }
+        println!("\ntop playlists:");
+        let pls = self.get_playlists()?;
+        for (uri, c) in per_playlist.iter().take(STATS_ROWS) {
+            let name = match pls.iter().find(|p| p.uri() == uri) {
+                Some(p) => p.name().to_string(),
+                None => {
+                    let id = uri.rsplit(':').next().unwrap_or_default();
+                    api::name_of(&self.client, "playlist", id).unwrap_or_else(|_| uri.clone())
+                }
+            };
+            print_count(c, &name);
+        }
+        Ok(())
+    }
+
+    pub fn features(&mut self, arg: Option<&str>) -> SpotifyResult {
+        let track = match arg {
+            None | Some("playing") => match self.playing_track()? {
+                Some(t) => t,
+                None => {
+                    println!("not playing anything");
+                    return Ok(());
+                }
+            },
+            Some(q) => match search::tracks(&self.client, &search::track_query(q), 1)?
+                .into_iter()
+                .next()
+            {
+                Some(t) => t,
+                None => {
+                    println!("no track found for {}", q);
+                    return Ok(());
+                }
+            },
+        };
+
+        self.show_features(&track)
+    }
+
+    pub fn show_features(&self, t: &FullTrack) -> SpotifyResult {
+        let id = match t.id.as_ref() {
+            Some(id) => id,
+            None => {
+                println!("{} is a local file, it has no audio features", &t.name);
+                return Ok(());
+            }
+        };
+        let f = self.client.audio_features(id)?;
+
+        let key = usize::try_from(f.key)
+            .ok()
+            .and_then(|k| KEYS.get(k))
+            .copied()
+            .unwrap_or("unknown");
+        let mode = if f.mode > 0.5 { "major" } else { "minor" };
+        println!("{} by {}", &t.name, crate::join_artists(&t.artists));
+        println!("tempo        = {:.1} bpm", f.tempo);
+        println!("key          = {} {}", key, mode);
+        println!("time sig.    = {}/4", f.time_signature);
+        println!("energy       = {:.2}", f.energy);
+        println!("danceability = {:.2}", f.danceability);
+        println!("valence      = {:.2}", f.valence);
+        println!("acousticness = {:.2}", f.acousticness);
+        println!("loudness     = {:.1} dB", f.loudness);
+        Ok(())
+    }
+
+    /// Tells for each track whether its audio features match all of the conditions.
+    pub fn filter_mask(
+        &self,
+        tracks: &[FullTrack],
+        conds: &[Condition],
+    ) -> Result<Vec<bool>, failure::Error> {
+        let ids = tracks
+            .iter()
+            .filter_map(|t| t.id.clone())
+            .collect::<Vec<_>>();
+        let features = self.audio_features(&ids)?;
+        Ok(tracks
+            .iter()
+            .map(|t| {
+                t.id.as_ref()
+                    .and_then(|id| features.get(id))
+                    .map_or(false, |f| conds.iter().all(|c| c.matches(f)))
+            })
+            .collect())
+    }
+
+    /// Resolves a seed and adds it to `seeds`.
+    /// Returns false if it couldn't be resolved; the reason is printed.
+    fn add_seed(&self, seeds: &mut Seeds, seed: &str) -> Result<bool, failure::Error> {
+        if seed.eq_ignore_ascii_case("playing") {
+            return Ok(match self.playing_track()? {
+                None => {
+                    println!("not playing anything, use --seed to choose a seed");
+                    false
+                }
+                Some(t) => {
+                    seeds.names.push(t.name.clone());
+                    seeds.tracks.push(t.id.unwrap_or(t.uri));
+                    true
+                }
+            });
+        }
+
+        let (kind, value) = match seed.split_once(':') {
+            Some((k, v)) if !v.trim().is_empty() => (k, v.trim()),
+            _ => {
+                println!("{} is not a valid seed", seed);
+                self.show_usage(Cmd::Recommend);
+                return Ok(false);
+            }
+        };
+        match &kind.to_lowercase()[..] {
+            "genre" => {
+                seeds.names.push(format!("genre {}", value));
+                seeds.genres.push(value.to_string());
+            }
+            "artist" => match search::artists(&self.client, value, 1)?.into_iter().next() {
+                Some(a) => {
+                    seeds.names.push(a.name);
+                    seeds.artists.push(a.id);
+                }
+                None => {
+                    println!("no artist found for {}", value);
+                    return Ok(false);
+                }
+            },
+            "track" => match search::tracks(&self.client, &search::track_query(value), 1)?
+                .into_iter()
+                .next()
+            {
+                Some(t) => {
+                    seeds.names.push(t.name);
+                    seeds.tracks.push(t.id.unwrap_or(t.uri));
+                }
+                None => {
+                    println!("no track found for {}", value);
+                    return Ok(false);
+                }
+            },
+            _ => {
+                println!("{} is not a valid seed", seed);
+                self.show_usage(Cmd::Recommend);
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Describes when and where from a track was played.
+fn played_note(t: &FullTrack, h: &PlayHistory, playlists: &HashMap<String, String>) -> String {
+    let at = h.played_at.with_timezone(&Local).format("%Y-%m-%d %H:%M");
+    let uri = match h.context.as_ref() {
+        Some(ctx) => &ctx.uri,
+        None => return at.to_string(),
+    };
+
+    let from = if uri.ends_with(":collection") {
+        String::from("liked songs")
+    } else if let Some(name) = playlists.get(uri) {
+        format!("playlist {}", name)
+    } else if uri.contains(":playlist:") {
+        String::from("a playlist")
+    } else if t.album.uri.as_ref() == Some(uri) {
+        format!("album {}", &t.album.name)
+    } else if let Some(a) = t.artists.iter().find(|a| a.uri.as_ref() == Some(uri)) {
+        format!("artist {}", &a.name)
+    } else {
+        uri.to_string()
+    };
+    format!("{}, from {}", at, from)
+}
+
+/// Adds a target value like `0.7` or a range like `120-130` for the attribute.
+fn tune(payload: &mut Map<String, Value>, attr: &str, value: &str) -> Result<(), String> {
+    let number = |s: &str| {
+        s.trim()
+            .parse::<Number>()
+            .map(Value::Number)
+            .map_err(|_| format!("{}: {} is not a number", attr, s))
+    };
+
+    // skip the first character so negative values like loudness are not read as ranges
+    match value.get(1..).and_then(|s| s.find('-')).map(|i| i + 1) {
+        Some(i) => {
+            payload.insert(format!("min_{}", attr), number(&value[..i])?);
+            payload.insert(format!("max_{}", attr), number(&value[i + 1..])?);
+        }
+        None => {
+            payload.insert(format!("target_{}", attr), number(value)?);
+        }
+    }
+    Ok(())
+}
+
+fn print_count(c: &Count, label: &str) {
+    println!(
+        "{:5} plays, {:3.0}% skipped | {}",
+        c.plays,
+        c.skip_rate(),
+        label
+    );
+}
@@ -0,0 +1,576 @@
+use super::search_cmd::{ArtistList, NextPage};
+use super::Controller;
+use crate::{
+    command::Cmd,
+    index::LibraryIndex,
+    playlist::{self, Playlist, SetOp},
+    read_bool, read_input, search, SpotifyResult,
+};
+use rspotify::model::{artist::FullArtist, playlist::PlaylistTrack, track::FullTrack};
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, HashSet};
+
+impl Controller {
+    pub fn playlist_set_op(&mut self, op: SetOp, arg: Option<&str>) -> SpotifyResult {
+        let cmd = match op {
+            SetOp::Merge => Cmd::Merge,
+            SetOp::Intersect => Cmd::Intersect,
+            SetOp::Subtract => Cmd::Subtract,
+        };
+        let args = match arg {
+            Some(a) => crate::split_args(a),
+            None => {
+                self.show_usage(cmd);
+                return Ok(());
+            }
+        };
+
+        let mut dedup = true;
+        let mut new = false;
+        let mut append = false;
+        let mut names = Vec::new();
+        for a in args {
+            match &a[..] {
+                "--keep-dupes" | "--keep-duplicates" => dedup = false,
+                "--new" => new = true,
+                "--append" => append = true,
+                s if s.starts_with("--") => {
+                    println!("unknown option {}", s);
+                    return Ok(());
+                }
+                _ => names.push(a),
+            }
+        }
+        if new && append {
+            println!("--new and --append can't be used together");
+            return Ok(());
+        }
+
+        let (a, b, c) = match names.iter().position(|s| s == "->") {
+            Some(2) if names.len() > 3 => (&names[0], &names[1], names[3..].join(" ")),
+            _ => {
+                self.show_usage(cmd);
+                return Ok(());
+            }
+        };
+
+        let a = match self.choose_user_playlist(Some(a))? {
+            Some(p) => p,
+            None => return Ok(()),
+        };
+        let b = match self.choose_user_playlist(Some(b))? {
+            Some(p) => p,
+            None => return Ok(()),
+        };
+
+        let result = op.apply(self.playable_tracks(&a)?, self.playable_tracks(&b)?, dedup);
+        let target = if new {
+            None
+        } else {
+            self.get_playlists()?.into_iter().find(|p| p.name_eq(&c))
+        };
+
+        self.write_playlist(target, &c, result, append)
+    }
+
+    pub fn copy_tracks(&mut self, arg: Option<&str>) -> SpotifyResult {
+        self.transfer_tracks(Cmd::CopyTracks, arg, false)
+    }
+
+    pub fn move_tracks(&mut self, arg: Option<&str>) -> SpotifyResult {
+        self.transfer_tracks(Cmd::MoveTracks, arg, true)
+    }
+
+    fn transfer_tracks(&mut self, cmd: Cmd, arg: Option<&str>, remove: bool) -> SpotifyResult {
+        let args = match arg.map(crate::split_args) {
+            Some(a) if a.len() >= 2 => a,
+            _ => {
+                self.show_usage(cmd);
+                return Ok(());
+            }
+        };
+
+        let from = match self.choose_user_playlist(Some(&args[0]))? {
+            Some(p) => p,
+            None => return Ok(()),
+        };
+        let to = match self.choose_user_playlist(Some(&args[1]))? {
+            Some(p) => p,
+            None => return Ok(()),
+        };
+        if from.id() == to.id() {
+            println!("the source and the destination are the same playlist");
+            return Ok(());
+        }
+        if to.owner().id != self.user {
+            println!("{} is not your playlist, can't add tracks to it", to.name());
+            return Ok(());
+        }
+        if remove && from.owner().id != self.user {
+            println!(
+                "{} is not your playlist, can't remove tracks from it",
+                from.name()
+            );
+            return Ok(());
+        }
+
+        // fetch the source again for an up to date snapshot id, positions depend on it
+        let from = self.fetch_playlist(from.id())?;
+        let items = from.all_tracks(&self.client, &self.user)?;
+        if items.is_empty() {
+            println!("{} is empty", from.name());
+            return Ok(());
+        }
+        let selected = match select_items(&items, &args[2..].join(" ")) {
+            Some(v) if v.is_empty() => {
+                println!("no track matched, no action taken");
+                return Ok(());
+            }
+            Some(v) => v,
+            None => {
+                println!("cancelled");
+                return Ok(());
+            }
+        };
+
+        if remove
+            && !read_bool(&format!(
+                "move {} tracks from {} to {}?",
+                selected.len(),
+                from.name(),
+                to.name()
+            ))
+        {
+            println!("cancelled");
+            return Ok(());
+        }
+
+        let mut dest = to.all_tracks(&self.client, &self.user)?;
+        let mut added = Vec::new();
+        for &i in &selected {
+            let t = items[i].track.as_ref().unwrap();
+            if dest.iter().any(|d| playlist::is_track(d, t)) {
+                println!(
+                    "= {} by {} (already in {})",
+                    &t.name,
+                    crate::join_artists(&t.artists),
+                    to.name()
+                );
+            } else {
+                println!("+ {} by {}", &t.name, crate::join_artists(&t.artists));
+                dest.push(items[i].clone());
+                added.push(t.clone());
+            }
+        }
+        self.add_tracks(to.id(), &added)?;
+        println!(
+            "copied {} tracks to {}, {} were already there",
+            added.len(),
+            to.name(),
+            selected.len() - added.len()
+        );
+
+        if remove {
+            // group the positions by uri, that's what the endpoint expects
+            let mut positions = BTreeMap::<&str, Vec<usize>>::new();
+            for &i in &selected {
+                let t = items[i].track.as_ref().unwrap();
+                positions.entry(&t.uri).or_default().push(i);
+                println!("- {} by {}", &t.name, crate::join_artists(&t.artists));
+            }
+            let objects = positions
+                .into_iter()
+                .map(|(uri, pos)| {
+                    let mut m = Map::new();
+                    m.insert("uri".to_string(), Value::from(uri));
+                    m.insert("positions".to_string(), json!(pos));
+                    m
+                })
+                .collect::<Vec<_>>();
+            for chunk in objects.chunks(100) {
+                self.client
+                    .user_playlist_remove_specific_occurrenes_of_tracks(
+                        &self.user,
+                        from.id(),
+                        chunk.to_vec(),
+                        Some(from.snapshot_id().to_string()),
+                    )?;
+            }
+            println!("removed {} tracks from {}", selected.len(), from.name());
+
+            let from = self.fetch_playlist(from.id())?;
+            self.replace_cached(from);
+        }
+
+        let to = self.fetch_playlist(to.id())?;
+        self.replace_cached(to);
+        Ok(())
+    }
+
+    pub fn where_track(&mut self, arg: Option<&str>) -> SpotifyResult {
+        let track = match arg {
+            None | Some("playing") => self.playing_track()?,
+            Some(a) if a.starts_with("spotify:track:") || a.contains("open.spotify.com/track/") => {
+                Some(self.client.track(a)?)
+            }
+            Some(a) => search::tracks(&self.client, &search::track_query(a), 1)?
+                .into_iter()
+                .next(),
+        };
+        let track = match track {
+            Some(t) => t,
+            None => {
+                println!("no track found");
+                return Ok(());
+            }
+        };
+
+        println!("{} by {}", &track.name, crate::join_artists(&track.artists));
+        let index = self.library_index()?;
+        let found = index.find(&track);
+        if found.is_empty() {
+            println!("none of your playlists contain this track");
+        }
+        for (pl, positions) in found {
+            println!(
+                "{}: {}",
+                pl.name(),
+                positions
+                    .iter()
+                    .map(|p| format!("#{}", p))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+        Ok(())
+    }
+
+    pub fn remove_everywhere(&mut self, track: &FullTrack) -> SpotifyResult {
+        let index = self.library_index()?;
+        let found = index
+            .find(track)
+            .into_iter()
+            .map(|(pl, _)| pl.clone())
+            .collect::<Vec<_>>();
+        if found.is_empty() {
+            println!(
+                "none of your playlists contain {}, no action taken",
+                &track.name
+            );
+            return Ok(());
+        }
+
+        for pl in &found {
+            println!("{}", pl.name());
+        }
+        if !read_bool(&format!(
+            "remove {} from these {} playlists?",
+            &track.name,
+            found.len()
+        )) {
+            println!("cancelled");
+            return Ok(());
+        }
+
+        let id = playlist::track_key(track).to_string();
+        for pl in found {
+            self.client.user_playlist_remove_all_occurrences_of_tracks(
+                &self.user,
+                pl.id(),
+                &[id.clone()],
+                None,
+            )?;
+            println!("removed from {}", pl.name());
+            let pl = self.fetch_playlist(pl.id())?;
+            self.replace_cached(pl);
+        }
+        Ok(())
+    }
+
+    /// Indexes the tracks of every playlist you own.
+    /// The last index is reused if none of the playlists changed since.
+    pub fn library_index(&mut self) -> Result<&LibraryIndex, failure::Error> {
+        // the cached playlists may have outdated snapshots
+        let pls = self
+            .fetch_playlists()?
+            .into_iter()
+            .filter(|p| p.owner().id == self.user)
+            .collect::<Vec<_>>();
+        if !self.index.as_ref().map_or(false, |i| i.is_current(&pls)) {
+            println!("indexing {} playlists...", pls.len());
+            self.index = Some(LibraryIndex::build(&self.client, &self.user, pls)?);
+        }
+        Ok(self.index.as_ref().unwrap())
+    }
+
+    pub fn saved_albums(&mut self, arg: Option<&str>) -> SpotifyResult {
+        let mut args = arg.unwrap_or_default().split_whitespace();
+        let key = args.next().map(|s| s.to_lowercase());
+        let desc = match args.next() {
+            // newest first by default
+            None => matches!(key.as_deref(), None | Some("added")),
+            Some(s) if s.eq_ignore_ascii_case("desc") => true,
+            Some(s) if s.eq_ignore_ascii_case("asc") => false,
+            Some(_) => {
+                self.show_usage(Cmd::SavedAlbums);
+                return Ok(());
+            }
+        };
+
+        let mut saved = Vec::new();
+        loop {
+            let page = self
+                .client
+                .current_user_saved_albums(50, saved.len() as u32)?;
+            let done = page.next.is_none() || page.items.is_empty();
+            saved.extend(page.items);
+            if done {
+                break;
+            }
+        }
+        if saved.is_empty() {
+            println!("you don't have any saved albums");
+            return Ok(());
+        }
+
+        match key.as_deref() {
+            None | Some("added") => saved.sort_by(|a, b| a.added_at.cmp(&b.added_at)),
+            Some("artist") => saved.sort_by_cached_key(|a| {
+                (
+                    a.album
+                        .artists
+                        .first()
+                        .map(|a| a.name.to_lowercase())
+                        .unwrap_or_default(),
+                    a.album.release_date.clone(),
+                )
+            }),
+            Some("release-date") | Some("release") | Some("date") => {
+                saved.sort_by(|a, b| a.album.release_date.cmp(&b.album.release_date))
+            }
+            Some(_) => {
+                self.show_usage(Cmd::SavedAlbums);
+                return Ok(());
+            }
+        }
+        if desc {
+            saved.reverse();
+        }
+
+        self.album_shell(
+            saved
+                .into_iter()
+                .map(|s| Self::simplify_album(s.album))
+                .collect(),
+        )
+    }
+
+    pub fn followed_artists(&mut self) -> SpotifyResult {
+        let page = self.client.current_user_followed_artists(50, None)?.artists;
+        if page.items.is_empty() {
+            println!("you are not following any artist");
+            return Ok(());
+        }
+        if let Some(total) = page.total {
+            println!("you are following {} artists", total);
+        }
+
+        // followed artists are paged with a cursor instead of an offset
+        let mut after = page.cursors.after.clone();
+        let next_page: NextPage<FullArtist> = Box::new(move |client| match after.take() {
+            None => Ok(Vec::new()),
+            Some(cursor) => {
+                let page = client
+                    .current_user_followed_artists(50, Some(cursor))?
+                    .artists;
+                after = page.cursors.after;
+                Ok(page.items)
+            }
+        });
+
+        self.artist_list_shell(ArtistList {
+            next_page: page.cursors.after.as_ref().map(|_| next_page),
+            artists: page.items,
+        })
+    }
+
+    /// Writes the tracks to the target playlist, creating a new playlist called `name` if there's no target.
+    /// If `append` is set, only the tracks that are not already in the target are added to its end;
+    /// otherwise the target's contents are replaced.
+    pub fn write_playlist(
+        &mut self,
+        target: Option<Playlist>,
+        name: &str,
+        mut tracks: Vec<FullTrack>,
+        append: bool,
+    ) -> SpotifyResult {
+        let pl = match target {
+            Some(pl) if pl.owner().id != self.user => {
+                println!("{} is not your playlist, can't modify it", pl.name());
+                return Ok(());
+            }
+            Some(pl) if append => {
+                let existing = self
+                    .playable_tracks(&pl)?
+                    .iter()
+                    .map(|t| playlist::track_key(t).to_string())
+                    .collect::<HashSet<_>>();
+                tracks.retain(|t| !existing.contains(playlist::track_key(t)));
+                if tracks.is_empty() {
+                    println!("{} already has all the tracks, no action taken", pl.name());
+                    return Ok(());
+                }
+                if !read_bool(&format!("add {} tracks to {}?", tracks.len(), pl.name())) {
+                    println!("cancelled");
+                    return Ok(());
+                }
+                self.add_tracks(pl.id(), &tracks)?;
+                println!("added {} tracks to {}", tracks.len(), pl.name());
+                pl
+            }
+            Some(pl) => {
+                if !read_bool(&format!(
+                    "replace the contents of {} with {} tracks?",
+                    pl.name(),
+                    tracks.len()
+                )) {
+                    println!("cancelled");
+                    return Ok(());
+                }
+                self.replace_tracks(pl.id(), &tracks)?;
+                println!("{} now has {} tracks", pl.name(), tracks.len());
+                pl
+            }
+            None => {
+                if !read_bool(&format!(
+                    "create playlist {} with {} tracks?",
+                    name,
+                    tracks.len()
+                )) {
+                    println!("cancelled");
+                    return Ok(());
+                }
+                let pl = Playlist::from(
+                    self.client
+                        .user_playlist_create(&self.user, name, None, None)?,
+                );
+                if let Some(v) = self.pl_cache.as_mut() {
+                    v.insert(0, pl.clone());
+                }
+                self.add_tracks(pl.id(), &tracks)?;
+                println!("created {} with {} tracks", pl.name(), tracks.len());
+                pl
+            }
+        };
+
+        let pl = self.fetch_playlist(pl.id())?;
+        self.replace_cached(pl);
+        Ok(())
+    }
+
+    /// Returns the tracks of a playlist, skipping local files and unavailable items.
+    pub fn playable_tracks(&self, pl: &Playlist) -> Result<Vec<FullTrack>, failure::Error> {
+        pl.all_tracks(&self.client, &self.user).map(|items| {
+            items
+                .into_iter()
+                .filter(|item| !item.is_local)
+                .filter_map(|item| item.track)
+                .collect()
+        })
+    }
+
+    /// Replaces the contents of a playlist with the tracks.
+    pub fn replace_tracks(&self, playlist_id: &str, tracks: &[FullTrack]) -> SpotifyResult {
+        // the endpoint accepts at most 100 tracks, the rest is added afterwards
+        let (first, rest) = tracks.split_at(tracks.len().min(100));
+        self.client
+            .user_playlist_replace_tracks(&self.user, playlist_id, &track_ids(first))?;
+        self.add_tracks(playlist_id, rest)
+    }
+
+    /// Appends the tracks to the end of a playlist, 100 at a time.
+    fn add_tracks(&self, playlist_id: &str, tracks: &[FullTrack]) -> SpotifyResult {
+        for chunk in tracks.chunks(100) {
+            self.client.user_playlist_add_tracks(
+                &self.user,
+                playlist_id,
+                &track_ids(chunk),
+                None,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn track_ids(tracks: &[FullTrack]) -> Vec<String> {
+    tracks
+        .iter()
+        .map(|t| playlist::track_key(t).to_string())
+        .collect()
+}
+
+/// Returns the indices of the items picked by `selection`, prompting for one if it's empty.
+/// Local files and unavailable items are never selected.
+fn select_items(items: &[PlaylistTrack], selection: &str) -> Option<Vec<usize>> {
+    let selection = if selection.is_empty() {
+        for (i, item) in items.iter().enumerate() {
+            if let Some(t) = item.track.as_ref() {
+                println!(
+                    "#{no:2} | {name} by {artist}",
+                    no = i,
+                    name = &t.name,
+                    artist = crate::join_artists(&t.artists)
+                );
+            }
+        }
+        let s = read_input("tracks (numbers like 0,3,7-9, `all` or a filter)");
+        if s.is_empty() {
+            return None;
+        }
+        s
+    } else {
+        selection.to_string()
+    };
+
+    let indices = if crate::equalfold(&selection, "all") {
+        (0..items.len()).collect()
+    } else if let Some(mut v) = crate::parse_indices(&selection) {
+        if let Some(n) = v.iter().find(|&&n| n >= items.len()) {
+            println!(
+                "{} is out of range, please enter numbers between 0 and {}",
+                n,
+                items.len() - 1
+            );
+            return None;
+        }
+        v.sort_unstable();
+        v.dedup();
+        v
+    } else {
+        let filter = selection.to_lowercase();
+        items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| {
+                item.track
+                    .as_ref()
+                    .map(|t| {
+                        t.name.to_lowercase().contains(&filter)
+                            || t.album.name.to_lowercase().contains(&filter)
+                            || t.artists
+                                .iter()
+                                .any(|a| a.name.to_lowercase().contains(&filter))
+                    })
+                    .unwrap_or(false)
+            })
+            .map(|(i, _)| i)
+            .collect()
+    };
+
+    Some(
+        indices
+            .into_iter()
+            .filter(|&i| !items[i].is_local && items[i].track.is_some())
+            .collect(),
+    )
+}
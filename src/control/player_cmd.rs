@@ -0,0 +1,862 @@
+use super::{Controller, TrackList};
+use crate::{
+    api,
+    command::Cmd,
+    smart,
+    task::{Stop, Task},
+    SpotifyResult,
+};
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode},
+    execute,
+    terminal::{self, Clear, ClearType},
+};
+use rspotify::{
+    blocking::client::Spotify,
+    model::{
+        context::{Context, CurrentlyPlaybackContext},
+        PlayingItem,
+    },
+    senum::AdditionalType,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, Write},
+    thread,
+    time::{Duration, Instant},
+};
+
+const DEFAULT_CARD: &str = "{name} by {artists}
+{album} ({year})
+{state} {bar} {elapsed} / {duration}
+context | {context}
+device  | {device} ({volume}%)
+shuffle | {shuffle}, repeat {repeat}
+library | {liked}";
+
+const BAR_WIDTH: usize = 30;
+
+/// How long the sleep timer takes to fade the volume out.
+const FADE: Duration = Duration::from_secs(60);
+/// How often the volume is changed while fading.
+const FADE_STEP: Duration = Duration::from_secs(5);
+
+/// A position given to `seek`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SeekTo {
+    Absolute(u32),
+    Relative(i64),
+    Percent(f64),
+}
+
+impl SeekTo {
+    fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        if let Some(p) = s.strip_suffix('%') {
+            return p
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|p| (0.0..=100.0).contains(p))
+                .map(Self::Percent);
+        }
+        match s.chars().next()? {
+            '+' => parse_time(&s[1..]).map(|ms| Self::Relative(i64::from(ms))),
+            '-' => parse_time(&s[1..]).map(|ms| Self::Relative(-i64::from(ms))),
+            _ => parse_time(s).map(Self::Absolute),
+        }
+    }
+
+    /// The position to seek to, clamped to the track.
+    fn resolve(self, progress_ms: u32, duration_ms: u32) -> u32 {
+        let ms = match self {
+            Self::Absolute(ms) => i64::from(ms),
+            Self::Relative(d) => i64::from(progress_ms) + d,
+            Self::Percent(p) => (f64::from(duration_ms) * p / 100.0) as i64,
+        };
+        ms.max(0).min(i64::from(duration_ms)) as u32
+    }
+}
+
+/// Parses `90`, `90s`, `2m`, `1h`, `1:23` or `1:02:03` into milliseconds.
+pub fn parse_time(s: &str) -> Option<u32> {
+    let s = s.trim();
+    if let Some(h) = s.strip_suffix('h') {
+        return h.trim().parse::<u32>().ok()?.checked_mul(3_600_000);
+    }
+    if let Some(m) = s.strip_suffix('m') {
+        return m.trim().parse::<u32>().ok()?.checked_mul(60_000);
+    }
+    let s = s.strip_suffix('s').unwrap_or(s);
+    let mut secs: u32 = 0;
+    for (i, part) in s.split(':').enumerate() {
+        // only hours:minutes:seconds
+        if i > 2 || part.is_empty() || !crate::is_digits(part) {
+            return None;
+        }
+        secs = secs
+            .checked_mul(60)?
+            .checked_add(part.parse::<u32>().ok()?)?;
+    }
+    secs.checked_mul(1000)
+}
+
+fn progress_bar(progress_ms: u32, duration_ms: u32) -> String {
+    let filled = if duration_ms == 0 {
+        0
+    } else {
+        (progress_ms as usize * BAR_WIDTH / duration_ms as usize).min(BAR_WIDTH)
+    };
+    format!("[{}{}]", "#".repeat(filled), "-".repeat(BAR_WIDTH - filled))
+}
+
+/// Replaces the `{placeholders}` in the template, unknown ones are kept as they are.
+/// The template is scanned once so placeholders inside the values are left alone.
+fn render(template: &str, fields: &HashMap<&str, String>) -> String {
+    let mut s = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(i) = rest.find('{') {
+        s.push_str(&rest[..i]);
+        let after = &rest[i + 1..];
+        match after
+            .find('}')
+            .and_then(|end| fields.get(&after[..end]).map(|v| (end, v)))
+        {
+            Some((end, v)) => {
+                s.push_str(v);
+                rest = &after[end + 1..];
+            }
+            None => {
+                s.push('{');
+                rest = after;
+            }
+        }
+    }
+    s.push_str(rest);
+    s
+}
+
+/// The card fields read from the playback itself.
+fn playback_fields(
+    playback: &CurrentlyPlaybackContext,
+    item: &PlayingItem,
+) -> HashMap<&'static str, String> {
+    let mut fields = HashMap::new();
+    match item {
+        PlayingItem::Track(t) => {
+            fields.insert("name", t.name.clone());
+            fields.insert("artists", crate::join_artists(&t.artists));
+            fields.insert("album", t.album.name.clone());
+            fields.insert(
+                "year",
+                smart::release_year(t).map_or_else(|| String::from("?"), |y| y.to_string()),
+            );
+        }
+        PlayingItem::Episode(e) => {
+            fields.insert("name", e.name.clone());
+            fields.insert("artists", e.show.publisher.clone());
+            fields.insert("album", e.show.name.clone());
+            fields.insert("year", e.release_date.chars().take(4).collect());
+        }
+    }
+
+    let progress = playback.progress_ms.unwrap_or_default();
+    let duration = item_duration(item);
+    fields.insert(
+        "state",
+        String::from(if playback.is_playing {
+            "playing"
+        } else {
+            "paused"
+        }),
+    );
+    fields.insert("bar", progress_bar(progress, duration));
+    fields.insert("elapsed", crate::format_duration(progress));
+    fields.insert("duration", crate::format_duration(duration));
+    fields.insert("device", playback.device.name.clone());
+    fields.insert("volume", playback.device.volume_percent.to_string());
+    fields.insert(
+        "shuffle",
+        String::from(if playback.shuffle_state { "on" } else { "off" }),
+    );
+    fields.insert("repeat", playback.repeat_state.as_str().to_string());
+    fields
+}
+
+fn item_uri(item: &PlayingItem) -> String {
+    match item {
+        PlayingItem::Track(t) => t.uri.clone(),
+        PlayingItem::Episode(e) => e.uri.clone(),
+    }
+}
+
+fn time_left(due: Instant) -> String {
+    crate::format_duration(due.saturating_duration_since(Instant::now()).as_millis() as u32)
+}
+
+/// Changes the volume gradually, returns `false` if cancelled before reaching `to`.
+pub fn ramp_volume(
+    client: &Spotify,
+    device: Option<String>,
+    from: u8,
+    to: u8,
+    over: Duration,
+    stop: &Stop,
+) -> Result<bool, failure::Error> {
+    let steps = (over.as_secs() / FADE_STEP.as_secs()).max(1) as i32;
+    let (from, to) = (i32::from(from), i32::from(to));
+    for i in 1..=steps {
+        if !stop.sleep(over / steps as u32) {
+            return Ok(false);
+        }
+        client.volume((from + (to - from) * i / steps) as u8, device.clone())?;
+    }
+    Ok(true)
+}
+
+/// Waits, fades the volume out if asked to, then pauses the playback.
+fn run_sleep_timer(
+    client: &Spotify,
+    device: Option<String>,
+    wait: Duration,
+    fade: bool,
+    stop: &Stop,
+) -> SpotifyResult {
+    let fade = if fade {
+        FADE.min(wait)
+    } else {
+        Duration::from_secs(0)
+    };
+    if !stop.sleep(wait - fade) {
+        return Ok(());
+    }
+
+    let mut volume = None;
+    if !fade.is_zero() {
+        if let Some(p) = client.current_playback(None, None)? {
+            let v = p.device.volume_percent.min(100) as u8;
+            volume = Some(v);
+            if !ramp_volume(client, device.clone(), v, 0, fade, stop)? {
+                // cancelled while fading
+                return client.volume(v, device);
+            }
+        }
+    }
+
+    client.pause_playback(device.clone())?;
+    // so the next playback isn't silent
+    if let Some(v) = volume {
+        client.volume(v, device)?;
+    }
+    stop.report(String::from("sleep timer: paused the playback"));
+    Ok(())
+}
+
+/// The length of a track or an episode.
+pub fn item_duration(item: &PlayingItem) -> u32 {
+    match item {
+        PlayingItem::Track(t) => t.duration_ms,
+        PlayingItem::Episode(e) => e.duration_ms,
+    }
+}
+
+impl Controller {
+    pub fn now_playing(&mut self) -> SpotifyResult {
+        match self.playback()? {
+            Some(playback) => {
+                let card = self.card(&playback)?;
+                println!("{}", card);
+            }
+            None => println!("not playing anything"),
+        }
+        if let Some(due) = self.task("sleep").and_then(|t| t.due) {
+            println!("sleep timer: pausing in {}", time_left(due));
+        }
+        Ok(())
+    }
+
+    /// The current playback, if something is playing.
+    fn playback(&mut self) -> Result<Option<CurrentlyPlaybackContext>, failure::Error> {
+        let playback = self.client.current_playback(
+            None,
+            Some(vec![AdditionalType::Track, AdditionalType::Episode]),
+        )?;
+        Ok(playback.filter(|p| p.item.is_some()).map(|p| {
+            self.playing = p.is_playing;
+            p
+        }))
+    }
+
+    /// Renders the now playing card from the config template.
+    fn card(&mut self, playback: &CurrentlyPlaybackContext) -> Result<String, failure::Error> {
+        let item = match playback.item.as_ref() {
+            Some(item) => item,
+            None => return Ok(String::from("not playing anything")),
+        };
+        let template = self.card_template();
+        let mut fields = playback_fields(playback, item);
+        fields.extend(self.library_fields(playback, item, &template, true)?);
+        Ok(render(&template, &fields))
+    }
+
+    fn card_template(&self) -> String {
+        self.config
+            .now_playing
+            .clone()
+            .unwrap_or_else(|| DEFAULT_CARD.to_string())
+    }
+
+    /// The card fields that need more requests, only the ones the template uses.
+    /// The playlist index is only rebuilt if `refresh_index` is set, rebuilding it prints progress.
+    fn library_fields(
+        &mut self,
+        playback: &CurrentlyPlaybackContext,
+        item: &PlayingItem,
+        template: &str,
+        refresh_index: bool,
+    ) -> Result<HashMap<&'static str, String>, failure::Error> {
+        let uses = |name: &str| template.contains(&format!("{{{}}}", name));
+        let mut fields = HashMap::new();
+
+        if uses("context") {
+            let ctx = match playback.context.as_ref() {
+                Some(ctx) => self.context_name(ctx, item)?,
+                None => String::from("none"),
+            };
+            fields.insert("context", ctx);
+        }
+        let t = match item {
+            PlayingItem::Track(t) => t,
+            PlayingItem::Episode(_) => {
+                fields.insert("liked", String::from("not a track"));
+                fields.insert("playlists", String::from("not a track"));
+                return Ok(fields);
+            }
+        };
+        if uses("liked") {
+            let id = t.id.clone().unwrap_or_else(|| t.uri.clone());
+            let liked = self
+                .client
+                .current_user_saved_tracks_contains(&[id])?
+                .first()
+                .copied()
+                .unwrap_or(false);
+            fields.insert(
+                "liked",
+                String::from(if liked { "liked" } else { "not liked" }),
+            );
+        }
+        if uses("playlists") {
+            let index = if refresh_index {
+                Some(self.library_index()?)
+            } else {
+                self.index.as_ref()
+            };
+            let names = index
+                .map(|i| i.find(t))
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(pl, _)| pl.name().to_string())
+                .collect::<Vec<_>>();
+            fields.insert(
+                "playlists",
+                if names.is_empty() {
+                    String::from("in none of your playlists")
+                } else {
+                    format!("in {}", names.join(", "))
+                },
+            );
+        }
+        Ok(fields)
+    }
+
+    pub fn watch(&mut self, arg: Option<&str>) -> SpotifyResult {
+        let interval = match arg.map(str::parse::<u64>) {
+            None => Duration::from_secs(2),
+            Some(Ok(n)) if n > 0 => Duration::from_secs(n),
+            _ => {
+                self.show_usage(Cmd::Watch);
+                return Ok(());
+            }
+        };
+
+        // index before raw mode, indexing the library prints progress
+        if self.card_template().contains("{playlists}") {
+            self.library_index()?;
+        }
+        let playback = self.playback()?;
+        println!(
+            "space: pause/resume, n: next, p: prev, +/-: volume, l: like, any other key: stop"
+        );
+
+        terminal::enable_raw_mode()?;
+        let res = self.watch_loop(interval, playback);
+        terminal::disable_raw_mode()?;
+        println!();
+        res
+    }
+
+    fn watch_loop(
+        &mut self,
+        interval: Duration,
+        mut playback: Option<CurrentlyPlaybackContext>,
+    ) -> SpotifyResult {
+        let mut out = io::stdout();
+        let mut lines = 0;
+        let mut status = String::new();
+        let mut last_uri = playback
+            .as_ref()
+            .and_then(|p| p.item.as_ref())
+            .map(item_uri);
+        let template = self.card_template();
+        // the fields that need more requests, kept until the track or context changes
+        let mut library: Option<(String, HashMap<&str, String>)> = None;
+
+        loop {
+            // redraw in place
+            let mut text = match playback
+                .as_ref()
+                .and_then(|p| p.item.as_ref().map(|item| (p, item)))
+            {
+                Some((p, item)) => {
+                    let key = format!(
+                        "{} {}",
+                        item_uri(item),
+                        p.context.as_ref().map_or("", |c| &c.uri[..])
+                    );
+                    if library.as_ref().map_or(true, |(k, _)| *k != key) {
+                        // on errors the last fields are kept and fetched again next time
+                        match self.library_fields(p, item, &template, false) {
+                            Ok(fields) => library = Some((key, fields)),
+                            Err(e) => status = format!("error: {}", e),
+                        }
+                    }
+                    let mut fields = playback_fields(p, item);
+                    if let Some((_, l)) = library.as_ref() {
+                        fields.extend(l.iter().map(|(k, v)| (*k, v.clone())));
+                    }
+                    render(&template, &fields)
+                }
+                None => String::from("not playing anything"),
+            };
+            if !status.is_empty() {
+                text.push('\n');
+                text.push_str(&status);
+            }
+            if lines > 0 {
+                execute!(out, cursor::MoveUp(lines))?;
+            }
+            execute!(
+                out,
+                cursor::MoveToColumn(0),
+                Clear(ClearType::FromCursorDown)
+            )?;
+            // raw mode doesn't return the cursor on new lines
+            print!("{}", text.replace('\n', "\r\n"));
+            out.flush()?;
+            lines = text.lines().count().saturating_sub(1) as u16;
+
+            let deadline = Instant::now() + interval;
+            while let Some(wait) = deadline.checked_duration_since(Instant::now()) {
+                if !event::poll(wait)? {
+                    break;
+                }
+                let key = match event::read()? {
+                    Event::Key(k) => k,
+                    _ => continue,
+                };
+                status = match self.watch_key(key.code, playback.as_ref()) {
+                    Ok(Some(s)) => s,
+                    Ok(None) => return Ok(()),
+                    Err(e) => format!("error: {}", e),
+                };
+                if key.code == KeyCode::Char('l') {
+                    library = None;
+                }
+                // give the player a moment to apply the change
+                thread::sleep(Duration::from_millis(300));
+                break;
+            }
+
+            // keep showing the last card if the playback can't be read
+            match self.playback() {
+                Ok(p) => playback = p,
+                Err(e) => {
+                    status = format!("error: {}", e);
+                    continue;
+                }
+            }
+            let uri = playback
+                .as_ref()
+                .and_then(|p| p.item.as_ref())
+                .map(item_uri);
+            if uri != last_uri {
+                if let Some(PlayingItem::Track(t)) = playback.as_ref().and_then(|p| p.item.as_ref())
+                {
+                    status = format!("track changed to {}", &t.name);
+                }
+                last_uri = uri;
+            }
+        }
+    }
+
+    /// Handles a key pressed while watching, returns the status to show or `None` to stop.
+    fn watch_key(
+        &mut self,
+        key: KeyCode,
+        playback: Option<&CurrentlyPlaybackContext>,
+    ) -> Result<Option<String>, failure::Error> {
+        // no device is activated here, choosing one would prompt in raw mode
+        let playing = playback.map_or(false, |p| p.is_playing);
+        let volume = playback.map_or(0, |p| p.device.volume_percent as i32);
+        Ok(Some(match key {
+            KeyCode::Char(' ') if playing => {
+                self.client.pause_playback(self.device.clone())?;
+                self.playing = false;
+                String::from("paused")
+            }
+            KeyCode::Char(' ') => {
+                self.client
+                    .start_playback(self.device.clone(), None, None, None, None)?;
+                self.playing = true;
+                String::from("resumed")
+            }
+            KeyCode::Char('n') => {
+                self.client.next_track(self.device.clone())?;
+                String::from("skipped to the next track")
+            }
+            KeyCode::Char('p') => {
+                self.client.previous_track(self.device.clone())?;
+                String::from("went back to the previous track")
+            }
+            KeyCode::Char(c @ '+') | KeyCode::Char(c @ '-') => {
+                let step = if c == '+' { 5 } else { -5 };
+                let n = (volume + step).clamp(0, 100);
+                self.client.volume(n as u8, self.device.clone())?;
+                format!("volume set to {}%", n)
+            }
+            KeyCode::Char('l') => match playback.and_then(|p| p.item.as_ref()) {
+                Some(PlayingItem::Track(t)) => {
+                    let id = t.id.clone().unwrap_or_else(|| t.uri.clone());
+                    self.client.current_user_saved_tracks_add(&[id])?;
+                    format!("liked {}", &t.name)
+                }
+                _ => String::from("not playing a track"),
+            },
+            _ => return Ok(None),
+        }))
+    }
+
+    /// Describes what the playback context is, like `playlist Gym`.
+    fn context_name(
+        &mut self,
+        ctx: &Context,
+        item: &PlayingItem,
+    ) -> Result<String, failure::Error> {
+        if ctx.uri.ends_with(":collection") {
+            return Ok(String::from("liked songs"));
+        }
+        // uris look like spotify:<kind>:<id>, old playlist uris also contain the owner
+        let mut parts = ctx.uri.rsplit(':');
+        let (id, kind) = match (parts.next(), parts.next()) {
+            (Some(id), Some(kind)) => (id, kind),
+            _ => return Ok(ctx.uri.clone()),
+        };
+
+        let known = match (kind, item) {
+            ("album", PlayingItem::Track(t)) if t.album.id.as_deref() == Some(id) => {
+                Some(t.album.name.clone())
+            }
+            ("artist", PlayingItem::Track(t)) => t
+                .artists
+                .iter()
+                .find(|a| a.id.as_deref() == Some(id))
+                .map(|a| a.name.clone()),
+            ("playlist", _) => self
+                .get_playlists()?
+                .iter()
+                .find(|p| p.id() == id)
+                .map(|p| p.name().to_string()),
+            _ => None,
+        };
+        let name = match known {
+            Some(name) => name,
+            None => api::name_of(&self.client, kind, id)?,
+        };
+        Ok(format!("{} {}", kind, name))
+    }
+
+    pub fn seek(&mut self, arg: Option<&str>) -> SpotifyResult {
+        let to = match arg.and_then(SeekTo::parse) {
+            Some(to) => to,
+            None => {
+                self.show_usage(Cmd::Seek);
+                return Ok(());
+            }
+        };
+
+        let playback = match self.playback()? {
+            Some(p) => p,
+            None => {
+                println!("not playing anything");
+                return Ok(());
+            }
+        };
+        let duration = playback.item.as_ref().map_or(0, item_duration);
+
+        let ms = to.resolve(playback.progress_ms.unwrap_or_default(), duration);
+        self.on_device(|c, dev| c.seek_track(ms, dev)).map(|_| {
+            println!(
+                "{} / {}",
+                crate::format_duration(ms),
+                crate::format_duration(duration)
+            );
+        })
+    }
+
+    pub fn show_queue(&mut self) -> SpotifyResult {
+        let q = api::queue(&self.client)?;
+        match q.currently_playing.as_ref() {
+            Some(PlayingItem::Track(t)) => {
+                println!(
+                    "now playing {} by {}",
+                    &t.name,
+                    crate::join_artists(&t.artists)
+                )
+            }
+            Some(PlayingItem::Episode(e)) => {
+                println!("now playing {} from {}", &e.name, &e.show.name)
+            }
+            None => println!("not playing anything"),
+        }
+
+        let queued = self.queued.borrow().len();
+        if queued > 0 {
+            println!(
+                "you added {} items to the queue with libman this session",
+                queued
+            );
+        }
+        if q.queue.is_empty() {
+            println!("there is nothing in the queue");
+            return Ok(());
+        }
+
+        let ours = self.queued.borrow().iter().cloned().collect::<HashSet<_>>();
+        let mut tracks = Vec::new();
+        let mut positions = Vec::new();
+        let mut episodes = 0;
+        for (i, item) in q.queue.into_iter().enumerate() {
+            match item {
+                PlayingItem::Track(t) => {
+                    let note = if ours.contains(&t.uri) {
+                        Some(String::from("queued with libman"))
+                    } else {
+                        None
+                    };
+                    tracks.push((t, note));
+                    positions.push(i);
+                }
+                PlayingItem::Episode(_) => episodes += 1,
+            }
+        }
+        if episodes > 0 {
+            println!("{} podcast episodes in the queue are not listed", episodes);
+        }
+        if tracks.is_empty() {
+            return Ok(());
+        }
+
+        self.track_list_shell(TrackList {
+            queue_positions: Some(positions),
+            ..TrackList::from(tracks)
+        })
+    }
+
+    pub fn sleep(&mut self, arg: Option<&str>) -> SpotifyResult {
+        let arg = match arg {
+            Some(a) => a,
+            None => {
+                match self.task("sleep").and_then(|t| t.due) {
+                    Some(due) => println!("pausing in {}", time_left(due)),
+                    None => self.show_usage(Cmd::Sleep),
+                }
+                return Ok(());
+            }
+        };
+
+        let mut fade = false;
+        let mut when = None;
+        for a in arg.split_whitespace() {
+            match a {
+                "--fade" => fade = true,
+                _ if when.is_none() => when = Some(a),
+                _ => {
+                    self.show_usage(Cmd::Sleep);
+                    return Ok(());
+                }
+            }
+        }
+
+        let wait = match when {
+            Some("cancel") => {
+                if self.cancel_task("sleep") {
+                    println!("cancelled the sleep timer");
+                } else {
+                    println!("there is no sleep timer running");
+                }
+                return Ok(());
+            }
+            Some("end-of-track") => self.playing_left(false)?,
+            Some("end-of-album") => self.playing_left(true)?,
+            Some(s) => match parse_time(s) {
+                Some(ms) if ms > 0 => Some(Duration::from_millis(u64::from(ms))),
+                _ => {
+                    self.show_usage(Cmd::Sleep);
+                    return Ok(());
+                }
+            },
+            None => {
+                self.show_usage(Cmd::Sleep);
+                return Ok(());
+            }
+        };
+        let wait = match wait {
+            Some(w) => w,
+            None => return Ok(()),
+        };
+
+        let client = self.client.clone();
+        let device = self.device.clone();
+        let task = Task::spawn("sleep", Some(Instant::now() + wait), move |stop| {
+            if let Err(e) = run_sleep_timer(&client, device, wait, fade, &stop) {
+                stop.report(format!("sleep timer: error: {}", e));
+            }
+        });
+        if self.start_task(task) {
+            println!("replaced the previous sleep timer");
+        }
+        println!(
+            "pausing in {}",
+            crate::format_duration(wait.as_millis() as u32)
+        );
+        Ok(())
+    }
+
+    /// How long until the playing track, or the rest of its album, ends.
+    fn playing_left(&mut self, album: bool) -> Result<Option<Duration>, failure::Error> {
+        let playback = match self.playback()? {
+            Some(p) => p,
+            None => {
+                println!("not playing anything");
+                return Ok(None);
+            }
+        };
+        let item = playback.item.as_ref().unwrap();
+        let mut ms = item_duration(item).saturating_sub(playback.progress_ms.unwrap_or_default());
+
+        if album {
+            let t = match item {
+                PlayingItem::Track(t) => t,
+                PlayingItem::Episode(_) => {
+                    println!("not playing an album");
+                    return Ok(None);
+                }
+            };
+            let tracks = self.album_tracks(&t.album)?;
+            if tracks.is_empty() {
+                println!("the playing track is not from an album on spotify");
+                return Ok(None);
+            }
+            // relinked tracks have another id, but keep their place on the album
+            let i = tracks
+                .iter()
+                .position(|x| {
+                    x.id == t.id
+                        || (x.disc_number, x.track_number) == (t.disc_number, t.track_number)
+                })
+                .ok_or_else(|| {
+                    failure::format_err!("could not find {} on {}", &t.name, &t.album.name)
+                })?;
+            ms += tracks[i + 1..].iter().map(|x| x.duration_ms).sum::<u32>();
+        }
+
+        Ok(Some(Duration::from_millis(u64::from(ms))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_times() {
+        assert_eq!(parse_time("90"), Some(90_000));
+        assert_eq!(parse_time("90s"), Some(90_000));
+        assert_eq!(parse_time("2m"), Some(120_000));
+        assert_eq!(parse_time("1h"), Some(3_600_000));
+        assert_eq!(parse_time("1:23"), Some(83_000));
+        assert_eq!(parse_time("1:02:03"), Some(3_723_000));
+        assert_eq!(parse_time(" 5m "), Some(300_000));
+    }
+
+    #[test]
+    fn rejects_invalid_times() {
+        for s in &[
+            "",
+            "s",
+            "m",
+            "abc",
+            "1.5m",
+            "1:",
+            ":30",
+            "1:2:3:4",
+            "-10",
+            "+5",
+            "5x",
+            // too long to fit in milliseconds
+            "5000000",
+            "2000h",
+            "99999999m",
+            "4294967296",
+            "99999:00:00",
+        ] {
+            assert_eq!(parse_time(s), None, "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn parses_seek_positions() {
+        assert_eq!(SeekTo::parse("90"), Some(SeekTo::Absolute(90_000)));
+        assert_eq!(SeekTo::parse("1:23"), Some(SeekTo::Absolute(83_000)));
+        assert_eq!(SeekTo::parse("+30"), Some(SeekTo::Relative(30_000)));
+        assert_eq!(SeekTo::parse("-10s"), Some(SeekTo::Relative(-10_000)));
+        assert_eq!(SeekTo::parse("50%"), Some(SeekTo::Percent(50.0)));
+        for s in &["", "+", "-", "150%", "-5%", "abc%", "x"] {
+            assert_eq!(SeekTo::parse(s), None, "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn renders_placeholders_once() {
+        let mut fields = HashMap::new();
+        fields.insert("name", String::from("{device} {bar}"));
+        fields.insert("device", String::from("phone"));
+        assert_eq!(
+            render("{name} on {device}", &fields),
+            "{device} {bar} on phone"
+        );
+        assert_eq!(render("{unknown} {{device}}", &fields), "{unknown} {phone}");
+        assert_eq!(render("{device", &fields), "{device");
+        assert_eq!(render("", &fields), "");
+    }
+
+    #[test]
+    fn resolves_within_the_track() {
+        assert_eq!(SeekTo::Absolute(90_000).resolve(0, 60_000), 60_000);
+        assert_eq!(SeekTo::Relative(30_000).resolve(10_000, 60_000), 40_000);
+        assert_eq!(SeekTo::Relative(-10_000).resolve(5_000, 60_000), 0);
+        assert_eq!(SeekTo::Percent(50.0).resolve(0, 60_000), 30_000);
+    }
+}
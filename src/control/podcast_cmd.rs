@@ -0,0 +1,118 @@
+use super::Controller;
+use crate::{api, command::Cmd, search, SpotifyResult};
+use rspotify::model::show::{FullEpisode, SimplifiedEpisode, SimplifiedShow};
+
+impl Controller {
+    pub fn play_first_episode(&mut self, arg: Option<&str>) -> SpotifyResult {
+        match self.first_episode(arg, Cmd::PlayFirstEpisode)? {
+            Some(e) => self.play_episode(&e, false),
+            None => Ok(()),
+        }
+    }
+
+    pub fn resume(&mut self, arg: Option<&str>) -> SpotifyResult {
+        match self.first_episode(arg, Cmd::Resume)? {
+            Some(e) => self.play_episode(&e, true),
+            None => Ok(()),
+        }
+    }
+
+    fn first_episode(
+        &self,
+        arg: Option<&str>,
+        cmd: Cmd,
+    ) -> Result<Option<SimplifiedEpisode>, failure::Error> {
+        let query = match arg {
+            Some(a) => a,
+            None => {
+                self.show_usage(cmd);
+                return Ok(None);
+            }
+        };
+        let first = search::episodes(&self.client, query, 1)?.into_iter().next();
+        if first.is_none() {
+            println!("no result for {}", query);
+        }
+        Ok(first)
+    }
+
+    pub fn saved_shows(&mut self) -> SpotifyResult {
+        let mut shows = Vec::new();
+        loop {
+            let page = self.client.get_saved_show(50, shows.len() as u32)?;
+            let done = page.next.is_none();
+            shows.extend(page.items.into_iter().map(|s| s.show));
+            if done {
+                break;
+            }
+        }
+        if shows.is_empty() {
+            println!("you have no saved shows");
+            return Ok(());
+        }
+        self.show_shell(shows)
+    }
+
+    /// Plays an episode from the beginning, or from where you left off if `resume` is set.
+    pub fn play_episode(&mut self, ep: &SimplifiedEpisode, resume: bool) -> SpotifyResult {
+        let position = if resume {
+            // the resume point in a listing may be stale
+            let full: FullEpisode = api::get(
+                &self.client,
+                &format!("episodes/{}", &ep.id),
+                &[("market", String::from("from_token"))],
+            )?;
+            match full.resume_point {
+                Some(r) if !r.fully_played => r.resume_position_ms,
+                _ => 0,
+            }
+        } else {
+            0
+        };
+
+        self.on_device(|c, dev| {
+            c.start_playback(dev, None, Some(vec![ep.uri.clone()]), None, Some(position))
+        })
+        .map(|_| {
+            if position > 0 {
+                println!(
+                    "playing {} from {}",
+                    &ep.name,
+                    crate::format_duration(position)
+                );
+            } else {
+                println!("playing {}", &ep.name);
+            }
+            self.playing = true;
+        })
+    }
+
+    pub fn play_show(&mut self, show: &SimplifiedShow) -> SpotifyResult {
+        self.on_device(|c, dev| c.start_playback(dev, Some(show.uri.clone()), None, None, None))
+            .map(|_| {
+                println!("playing {}", &show.name);
+                self.playing = true;
+            })
+    }
+
+    /// Opens the latest episodes of a show in a nested shell.
+    pub fn show_episodes(&mut self, show: &SimplifiedShow) -> SpotifyResult {
+        let episodes = self
+            .client
+            .get_shows_episodes(show.id.clone(), 50, 0, None)?
+            .items;
+        if episodes.is_empty() {
+            println!("{} has no episodes", &show.name);
+            return Ok(());
+        }
+        println!("{} by {}", &show.name, &show.publisher);
+        self.nested(show.name.clone(), |c| c.episode_shell(episodes))
+    }
+
+    /// Saves an episode to "Your Episodes".
+    pub fn save_episode(&self, id: &str, name: &str) -> SpotifyResult {
+        api::put(&self.client, "me/episodes", &[("ids", id.to_string())]).map(|_| {
+            println!("saved {} to your episodes", name);
+        })
+    }
+}
@@ -0,0 +1,267 @@
+use super::{player_cmd, Controller};
+use crate::{
+    command::Cmd,
+    schedule::{self, Clock, Schedule},
+    split_args, split_command,
+    task::Task,
+    SpotifyResult,
+};
+use chrono::NaiveTime;
+use std::time::Duration;
+
+/// How often the scheduler checks for due schedules.
+const TICK: Duration = Duration::from_secs(15);
+
+impl Controller {
+    pub fn at(&mut self, arg: Option<&str>) -> SpotifyResult {
+        let args = match arg {
+            Some(a) => split_args(a),
+            None => return self.show_schedules(),
+        };
+        if let Some("remove") | Some("rm") = args.first().map(|s| &s[..]) {
+            return self.remove_schedule(args.get(1).map(|s| &s[..]));
+        }
+
+        let mut device = None;
+        let mut volume = None;
+        let mut fade_in = None;
+        let mut rest = Vec::new();
+        let mut it = args.into_iter();
+        while let Some(a) = it.next() {
+            match &a[..] {
+                "--device" => match it.next() {
+                    Some(d) => device = Some(d),
+                    None => {
+                        self.show_usage(Cmd::At);
+                        return Ok(());
+                    }
+                },
+                "--volume" => match it.next().and_then(|s| s.parse::<u8>().ok()) {
+                    Some(n) if n <= 100 => volume = Some(n),
+                    _ => {
+                        println!("the volume must be a number between 0 and 100");
+                        return Ok(());
+                    }
+                },
+                "--fade-in" => match it.next().and_then(|s| player_cmd::parse_time(&s)) {
+                    Some(ms) if ms > 0 => fade_in = Some(ms / 1000),
+                    _ => {
+                        println!("the fade in time must be like 90s or 5m");
+                        return Ok(());
+                    }
+                },
+                _ => rest.push(a),
+            }
+        }
+
+        let time = match rest
+            .first()
+            .and_then(|s| NaiveTime::parse_from_str(s, "%H:%M").ok())
+        {
+            Some(t) => t,
+            None => {
+                self.show_usage(Cmd::At);
+                return Ok(());
+            }
+        };
+        let (days, start) = match rest.get(1).and_then(|s| schedule::parse_days(s)) {
+            Some(days) => (days, 2),
+            None => (Vec::new(), 1),
+        };
+        // quote the arguments back so the command parses the same way later
+        let command = rest[start..]
+            .iter()
+            .map(|s| {
+                if s.contains(char::is_whitespace) {
+                    format!("\"{}\"", s)
+                } else {
+                    s.clone()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        let (name, cmd_args) = split_command(&command);
+        match self.handlers.iter().find(|h| h.is_match(name)) {
+            None if command.is_empty() => {
+                self.show_usage(Cmd::At);
+                return Ok(());
+            }
+            None => {
+                println!("{} is not a known command", name);
+                return Ok(());
+            }
+            Some(h) if h.cmd.is_interactive(cmd_args) => {
+                println!("{} asks for input so it can't be scheduled", name);
+                return Ok(());
+            }
+            Some(_) => (),
+        };
+
+        // the device is looked up again when the schedule runs, but a typo is caught now
+        if let Some(d) = device.as_deref() {
+            self.find_device(d)?;
+        }
+
+        let s = Schedule {
+            device,
+            volume,
+            fade_in,
+            ..Schedule::new(time, days, command, schedule::SystemClock.now())
+        };
+        let mut schedules = schedule::load(&self.schedule_path)?;
+        println!("scheduled {}", &s);
+        schedules.push(s);
+        schedule::save(&self.schedule_path, &schedules)
+    }
+
+    fn show_schedules(&self) -> SpotifyResult {
+        let schedules = schedule::load(&self.schedule_path)?;
+        if schedules.is_empty() {
+            println!("there are no schedules");
+        }
+        for (i, s) in schedules.iter().enumerate() {
+            println!("#{:2} | {}", i, s);
+        }
+        Ok(())
+    }
+
+    fn remove_schedule(&mut self, arg: Option<&str>) -> SpotifyResult {
+        let mut schedules = schedule::load(&self.schedule_path)?;
+        let n = match arg.and_then(|s| s.parse::<usize>().ok()) {
+            Some(n) if n < schedules.len() => n,
+            Some(_) if schedules.is_empty() => {
+                println!("there are no schedules");
+                return Ok(());
+            }
+            Some(_) => {
+                println!(
+                    "please enter a number between 0 and {}",
+                    schedules.len() - 1
+                );
+                return Ok(());
+            }
+            None => {
+                self.show_usage(Cmd::At);
+                return Ok(());
+            }
+        };
+        let s = schedules.remove(n);
+        schedule::save(&self.schedule_path, &schedules).map(|_| {
+            println!("removed {}", &s);
+        })
+    }
+
+    /// Runs the saved schedules as they come due, until the process is stopped.
+    pub fn run_schedules(&mut self, clock: &dyn Clock) -> SpotifyResult {
+        crate::set_interactive(false);
+        self.start_watcher();
+        let mut last = clock.now();
+        let schedules = schedule::load(&self.schedule_path)?;
+        let (missed, schedules): (Vec<_>, Vec<_>) =
+            schedules.into_iter().partition(|s| s.is_missed(last));
+        if !missed.is_empty() {
+            for s in &missed {
+                println!("missed {}", s);
+            }
+            schedule::save(&self.schedule_path, &schedules)?;
+        }
+        println!(
+            "waiting for {} schedules, press ctrl-c to stop",
+            schedules.len()
+        );
+
+        loop {
+            clock.sleep(TICK);
+            self.reap_tasks();
+            self.print_reports();
+            // the schedules may have been changed with `at` in the meantime
+            let schedules = match schedule::load(&self.schedule_path) {
+                Ok(s) => s,
+                Err(e) => {
+                    println!("error: {}", e);
+                    continue;
+                }
+            };
+
+            let tick = schedule::tick(clock, schedules, last);
+            if tick.due.iter().any(Schedule::is_once) {
+                if let Err(e) = schedule::save(&self.schedule_path, &tick.rest) {
+                    println!("error: {}", e);
+                }
+            }
+            last = tick.now;
+
+            if tick.due.is_empty() {
+                continue;
+            }
+            // the token expires after an hour, the client then loses access to the player
+            if let Err(e) = self.refresh_client() {
+                println!("error: {}", e);
+                continue;
+            }
+            for s in &tick.due {
+                println!("{}: running {}", tick.now.format("%Y-%m-%d %H:%M"), s);
+                if let Err(e) = self.run_schedule(s) {
+                    println!("error: {}", e);
+                }
+            }
+        }
+    }
+
+    /// Runs the command of a schedule through the same path as the prompt.
+    fn run_schedule(&mut self, s: &Schedule) -> SpotifyResult {
+        if let Some(name) = s.device.as_deref() {
+            let dev = self.find_device(name)?;
+            self.client.transfer_playback(&dev.id, false)?;
+            println!("playing on {}", &dev.name);
+            self.device = Some(dev.id);
+        }
+
+        let fade = s.fade_in.map(|secs| Duration::from_secs(u64::from(secs)));
+        let mut volume = s.volume;
+        if fade.is_some() {
+            if volume.is_none() {
+                volume = self
+                    .client
+                    .current_playback(None, None)?
+                    .map(|p| p.device.volume_percent.min(100) as u8);
+            }
+            if volume.is_some() {
+                self.on_device(|c, dev| c.volume(0, dev))?;
+            }
+        }
+
+        let mut res = self.run(&s.command);
+        if res.is_ok() && crate::skipped_prompt() {
+            res = Err(failure::format_err!(
+                "{} asked for input, it can't run unattended",
+                &s.command
+            ));
+        }
+        if let Err(e) = res {
+            // don't leave the device muted
+            if let (Some(_), Some(v)) = (fade, volume) {
+                if let Err(e) = self.client.volume(v, self.device.clone()) {
+                    println!("error: {}", e);
+                }
+            }
+            return Err(e);
+        }
+
+        match (fade, volume) {
+            (Some(over), Some(v)) => {
+                let client = self.client.clone();
+                let device = self.device.clone();
+                let task = Task::spawn("fade-in", None, move |stop| {
+                    if let Err(e) = player_cmd::ramp_volume(&client, device, 0, v, over, &stop) {
+                        stop.report(format!("fade in error: {}", e));
+                    }
+                });
+                self.start_task(task);
+                Ok(())
+            }
+            (None, Some(v)) => self.exec_cmd(Cmd::SetVolume, Some(&v.to_string())),
+            _ => Ok(()),
+        }
+    }
+}
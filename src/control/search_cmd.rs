@@ -1,15 +1,228 @@
 mod album_cmd;
 mod artist_cmd;
+mod episode_cmd;
 mod playlist_cmd;
+mod show_cmd;
 mod track_cmd;
 
 pub use super::Controller;
 use crate::{
-    command::{AlbumCmd, ArtistCmd, Cmd, PlaylistCmd, TrackCmd},
+    command::{AlbumCmd, ArtistCmd, Cmd, EpisodeCmd, PlaylistCmd, ShowCmd, TrackCmd},
     playlist::Playlist,
-    read_number, search, SpotifyResult,
+    read_number, search,
+    smart::Condition,
+    SpotifyResult,
 };
-use rspotify::model::{album::SimplifiedAlbum, artist::FullArtist, track::FullTrack};
+use rspotify::{
+    blocking::client::Spotify,
+    model::{
+        album::SimplifiedAlbum,
+        artist::FullArtist,
+        show::{SimplifiedEpisode, SimplifiedShow},
+        track::FullTrack,
+    },
+};
+
+/// Loads the next page of a listing, an empty result means there's nothing left.
+pub type NextPage<T> = Box<dyn FnMut(&Spotify) -> Result<Vec<T>, failure::Error>>;
+
+/// A track with an optional note shown next to it, like when it was played.
+pub type Noted = (FullTrack, Option<String>);
+
+/// The tracks shown in a track shell.
+pub struct TrackList {
+    pub tracks: Vec<FullTrack>,
+    /// One for each track.
+    pub notes: Vec<Option<String>>,
+    /// If set, tracks are played within this context instead of on their own.
+    pub context: Option<String>,
+    /// The tracks are an album's tracklist in order, so they're shown with their
+    /// track numbers and played by position within the context, or by uri once filtered.
+    pub album_order: bool,
+    pub next_page: Option<NextPage<Noted>>,
+    /// If set, the tracks are the upcoming items of the player queue at these positions,
+    /// playing one skips ahead to it.
+    pub queue_positions: Option<Vec<usize>>,
+    /// Conditions set with `filter`, they also apply to the tracks loaded later.
+    pub filters: Vec<Condition>,
+}
+
+impl From<Vec<FullTrack>> for TrackList {
+    fn from(tracks: Vec<FullTrack>) -> Self {
+        Self {
+            notes: vec![None; tracks.len()],
+            tracks,
+            context: None,
+            album_order: false,
+            next_page: None,
+            queue_positions: None,
+            filters: Vec::new(),
+        }
+    }
+}
+
+impl From<Vec<Noted>> for TrackList {
+    fn from(entries: Vec<Noted>) -> Self {
+        let (tracks, notes): (Vec<FullTrack>, _) = entries.into_iter().unzip();
+        Self {
+            notes,
+            ..Self::from(tracks)
+        }
+    }
+}
+
+/// The artists shown in an artist shell.
+pub struct ArtistList {
+    pub artists: Vec<FullArtist>,
+    pub next_page: Option<NextPage<FullArtist>>,
+}
+
+impl From<Vec<FullArtist>> for ArtistList {
+    fn from(artists: Vec<FullArtist>) -> Self {
+        Self {
+            artists,
+            next_page: None,
+        }
+    }
+}
+
+/// The albums shown in an album shell.
+pub struct AlbumList {
+    pub albums: Vec<SimplifiedAlbum>,
+    pub next_page: Option<NextPage<SimplifiedAlbum>>,
+}
+
+impl From<Vec<SimplifiedAlbum>> for AlbumList {
+    fn from(albums: Vec<SimplifiedAlbum>) -> Self {
+        Self {
+            albums,
+            next_page: None,
+        }
+    }
+}
+
+/// Parses the number of an item in a list of `len`, saying so if it's out of range.
+/// On `None` the shell shows the usage of its command.
+fn parse_index(len: usize, arg: Option<&str>) -> Option<usize> {
+    let n = arg?.trim().parse::<usize>().ok()?;
+    if n >= len {
+        println!(
+            "please enter a number between 0 and {}",
+            len.saturating_sub(1)
+        );
+        return None;
+    }
+    Some(n)
+}
+
+fn print_albums(albums: &[SimplifiedAlbum], start: usize) {
+    for (i, a) in albums.iter().enumerate() {
+        println!(
+            "#{no:2} | {name} by {artist}",
+            no = i + start,
+            name = &a.name,
+            artist = crate::join_artists(&a.artists)
+        );
+    }
+}
+
+fn print_shows(shows: &[SimplifiedShow]) {
+    for (i, s) in shows.iter().enumerate() {
+        println!(
+            "#{no:2} | {name} by {publisher}",
+            no = i,
+            name = &s.name,
+            publisher = &s.publisher
+        );
+    }
+}
+
+fn print_episodes(episodes: &[SimplifiedEpisode]) {
+    for (i, e) in episodes.iter().enumerate() {
+        let progress = match e.resume_point.as_ref() {
+            Some(r) if r.fully_played => String::from(", played"),
+            Some(r) if r.resume_position_ms > 0 => {
+                format!(", at {}", crate::format_duration(r.resume_position_ms))
+            }
+            _ => String::new(),
+        };
+        println!(
+            "#{no:2} | {name} ({date}, {duration}{progress})",
+            no = i,
+            name = &e.name,
+            date = &e.release_date,
+            duration = crate::format_duration(e.duration_ms),
+            progress = progress
+        );
+    }
+}
+
+fn print_artists(artists: &[FullArtist], start: usize) {
+    for (i, a) in artists.iter().enumerate() {
+        let followers = a
+            .followers
+            .get("total")
+            .and_then(|v| v.as_ref())
+            .and_then(|v| v.as_u64())
+            .unwrap_or_default();
+        if a.genres.is_empty() {
+            println!(
+                "#{no:2} | {name} ({followers} followers)",
+                no = i + start,
+                name = &a.name,
+                followers = followers
+            );
+        } else {
+            println!(
+                "#{no:2} | {name} ({followers} followers) [{genres}]",
+                no = i + start,
+                name = &a.name,
+                followers = followers,
+                genres = a.genres.join(", ")
+            );
+        }
+    }
+}
+
+fn print_tracks(tracks: &[FullTrack], notes: &[Option<String>], start: usize, album_order: bool) {
+    if album_order {
+        let multi_disc = tracks.iter().any(|t| t.disc_number > 1);
+        for (i, t) in tracks.iter().enumerate() {
+            let number = if multi_disc {
+                format!("{}-{:02}", t.disc_number, t.track_number)
+            } else {
+                format!("{:2}", t.track_number)
+            };
+            println!(
+                "#{no:2} | {number}. {name} ({duration}){explicit}",
+                no = i + start,
+                number = number,
+                name = &t.name,
+                duration = crate::format_duration(t.duration_ms),
+                explicit = if t.explicit { " [E]" } else { "" }
+            );
+        }
+        return;
+    }
+
+    for (i, t) in tracks.iter().enumerate() {
+        match notes.get(i).and_then(|n| n.as_ref()) {
+            Some(note) => println!(
+                "#{no:2} | {name} by {artist} ({note})",
+                no = i + start,
+                name = &t.name,
+                artist = crate::join_artists(&t.artists),
+                note = note
+            ),
+            None => println!(
+                "#{no:2} | {name} by {artist}",
+                no = i + start,
+                name = &t.name,
+                artist = crate::join_artists(&t.artists)
+            ),
+        }
+    }
+}
 
 enum SearchResult {
     Track(FullTrack),
@@ -142,6 +355,44 @@ impl Controller {
         self.playlist_shell(pls)
     }
 
+    pub fn search_show(&mut self, arg: Option<&str>) -> SpotifyResult {
+        let query = match arg {
+            Some(a) => a,
+            None => {
+                self.show_usage(Cmd::SearchShow);
+                return Ok(());
+            }
+        };
+
+        let shows = search::shows(&self.client, query, 20)?;
+
+        if shows.is_empty() {
+            println!("no result for {}", query);
+            return Ok(());
+        }
+
+        self.show_shell(shows)
+    }
+
+    pub fn search_episode(&mut self, arg: Option<&str>) -> SpotifyResult {
+        let query = match arg {
+            Some(a) => a,
+            None => {
+                self.show_usage(Cmd::SearchEpisode);
+                return Ok(());
+            }
+        };
+
+        let episodes = search::episodes(&self.client, query, 20)?;
+
+        if episodes.is_empty() {
+            println!("no result for {}", query);
+            return Ok(());
+        }
+
+        self.episode_shell(episodes)
+    }
+
     pub fn search(&mut self, arg: Option<&str>) -> SpotifyResult {
         let query = match arg {
             Some(a) => a,
@@ -171,7 +422,7 @@ impl Controller {
             r.println(i);
         }
 
-        if let Some(n) = read_number(0, results.len()) {
+        if let Some(n) = read_number(0, results.len() - 1) {
             match results.get(n).unwrap() {
                 SearchResult::Track(t) => self.play_track(t),
                 SearchResult::Artist(a) => self.play_artist(a),
@@ -184,20 +435,41 @@ impl Controller {
         }
     }
 
+    /// Runs a shell opened from another one, labelling the prompt with `label`.
+    pub fn nested<F>(&mut self, label: String, shell: F) -> SpotifyResult
+    where
+        F: FnOnce(&mut Self) -> SpotifyResult,
+    {
+        self.crumbs.push(label);
+        let res = shell(self);
+        self.crumbs.pop();
+        res
+    }
+
+    /// The prompt of a nested shell, what the background tasks reported is printed before it.
+    fn shell_prompt(&self) -> String {
+        self.print_reports();
+        if self.crumbs.is_empty() {
+            String::from("command:")
+        } else {
+            format!("{}:", self.crumbs.join(" > "))
+        }
+    }
+
     pub fn track_shell(&mut self, tracks: Vec<FullTrack>) -> SpotifyResult {
+        self.track_list_shell(TrackList::from(tracks))
+    }
+
+    pub fn track_list_shell(&mut self, mut list: TrackList) -> SpotifyResult {
         // show the tracks
-        for (i, t) in tracks.iter().enumerate() {
-            println!(
-                "#{no:2} | {name} by {artist}",
-                no = i,
-                name = &t.name,
-                artist = crate::join_artists(&t.artists)
-            );
+        print_tracks(&list.tracks, &list.notes, 0, list.album_order);
+        if list.next_page.is_some() {
+            println!("type `more` to load more tracks");
         }
 
         println!("type help for a list of available actions");
         loop {
-            let input = crate::prompt("command:");
+            let input = crate::prompt(&self.shell_prompt());
             if input.is_empty() {
                 println!("cancelled");
                 return Ok(());
@@ -205,10 +477,10 @@ impl Controller {
             let (cmd, arg) = crate::split_command(&input);
             if arg.is_none() && crate::is_digits(cmd) {
                 let n = cmd.parse::<usize>().unwrap();
-                if n < tracks.len() {
-                    return self.play_track(&tracks[n]);
+                if n < list.tracks.len() {
+                    return self.play_from_list(&list, n);
                 } else {
-                    println!("please enter a number between 0 and {}", tracks.len());
+                    println!("please enter a number between 0 and {}", list.tracks.len());
                 }
             } else {
                 let c = match self.track_handlers.iter().find(|h| h.is_match(cmd)) {
@@ -220,14 +492,21 @@ impl Controller {
                 };
 
                 let should_return = match c {
-                    TrackCmd::Play => self.track_cmd_play(&tracks, arg)?,
+                    TrackCmd::Play => self.track_cmd_play(&list, arg)?,
                     TrackCmd::Help => {
                         self.track_cmd_help(arg);
                         false
                     }
-                    TrackCmd::Queue => self.track_cmd_queue(&tracks, arg)?,
-                    TrackCmd::Save => self.track_cmd_save(&tracks, arg)?,
-                    TrackCmd::Like => self.track_cmd_like(&tracks, arg)?,
+                    TrackCmd::Queue => self.track_cmd_queue(&list.tracks, arg)?,
+                    TrackCmd::Save => self.track_cmd_save(&list.tracks, arg)?,
+                    TrackCmd::Like => self.track_cmd_like(&list.tracks, arg)?,
+                    TrackCmd::Unlike => self.track_cmd_unlike(&list.tracks, arg)?,
+                    TrackCmd::QueueAll => self.track_cmd_queue_all(&list.tracks)?,
+                    TrackCmd::SaveAll => self.track_cmd_save_all(&list.tracks, arg)?,
+                    TrackCmd::Features => self.track_cmd_features(&list.tracks, arg)?,
+                    TrackCmd::Filter => self.track_cmd_filter(&mut list, arg)?,
+                    TrackCmd::More => self.track_cmd_more(&mut list)?,
+                    TrackCmd::Back => true,
                 };
 
                 if should_return {
@@ -237,15 +516,20 @@ impl Controller {
         }
     }
 
-    fn artist_shell(&mut self, artists: Vec<FullArtist>) -> SpotifyResult {
+    pub fn artist_shell(&mut self, artists: Vec<FullArtist>) -> SpotifyResult {
+        self.artist_list_shell(ArtistList::from(artists))
+    }
+
+    pub fn artist_list_shell(&mut self, mut list: ArtistList) -> SpotifyResult {
         // show artists
-        for (i, a) in artists.iter().enumerate() {
-            println!("#{no:2} | {name}", no = i, name = &a.name);
+        print_artists(&list.artists, 0);
+        if list.next_page.is_some() {
+            println!("type `more` to load more artists");
         }
 
         println!("type help for a list of available actions");
         loop {
-            let input = crate::prompt("command:");
+            let input = crate::prompt(&self.shell_prompt());
             if input.is_empty() {
                 println!("cancelled");
                 return Ok(());
@@ -253,10 +537,10 @@ impl Controller {
             let (cmd, arg) = crate::split_command(&input);
             if arg.is_none() && crate::is_digits(cmd) {
                 let n = cmd.parse::<usize>().unwrap();
-                if n < artists.len() {
-                    return self.play_artist(&artists[n]);
+                if n < list.artists.len() {
+                    return self.play_artist(&list.artists[n]);
                 } else {
-                    println!("please enter a number between 0 and {}", artists.len());
+                    println!("please enter a number between 0 and {}", list.artists.len());
                 }
             } else {
                 let c = match self.artist_handlers.iter().find(|h| h.is_match(cmd)) {
@@ -267,13 +551,20 @@ impl Controller {
                     }
                 };
 
+                let artists = &list.artists;
                 let should_return = match c {
-                    ArtistCmd::Play => self.artist_cmd_play(&artists, arg)?,
+                    ArtistCmd::Play => self.artist_cmd_play(artists, arg)?,
                     ArtistCmd::Help => {
                         self.artist_cmd_help(arg);
                         false
                     }
-                    ArtistCmd::Follow => self.artist_cmd_follow(&artists, arg)?,
+                    ArtistCmd::Follow => self.artist_cmd_follow(artists, arg)?,
+                    ArtistCmd::Unfollow => self.artist_cmd_unfollow(artists, arg)?,
+                    ArtistCmd::Top => self.artist_cmd_top(artists, arg)?,
+                    ArtistCmd::Albums => self.artist_cmd_albums(artists, arg)?,
+                    ArtistCmd::Related => self.artist_cmd_related(artists, arg)?,
+                    ArtistCmd::More => self.artist_cmd_more(&mut list)?,
+                    ArtistCmd::Back => true,
                 };
 
                 if should_return {
@@ -283,20 +574,20 @@ impl Controller {
         }
     }
 
-    fn album_shell(&mut self, albums: Vec<SimplifiedAlbum>) -> SpotifyResult {
+    pub fn album_shell(&mut self, albums: Vec<SimplifiedAlbum>) -> SpotifyResult {
+        self.album_list_shell(AlbumList::from(albums))
+    }
+
+    pub fn album_list_shell(&mut self, mut list: AlbumList) -> SpotifyResult {
         // show albums
-        for (i, a) in albums.iter().enumerate() {
-            println!(
-                "#{no:2} | {name} by {artist}",
-                no = i,
-                name = &a.name,
-                artist = crate::join_artists(&a.artists)
-            );
+        print_albums(&list.albums, 0);
+        if list.next_page.is_some() {
+            println!("type `more` to load more albums");
         }
 
         println!("type help for a list of available actions");
         loop {
-            let input = crate::prompt("command:");
+            let input = crate::prompt(&self.shell_prompt());
             if input.is_empty() {
                 println!("cancelled");
                 return Ok(());
@@ -304,10 +595,10 @@ impl Controller {
             let (cmd, arg) = crate::split_command(&input);
             if arg.is_none() && crate::is_digits(cmd) {
                 let n = cmd.parse::<usize>().unwrap();
-                if n < albums.len() {
-                    return self.play_album(&albums[n]);
+                if n < list.albums.len() {
+                    return self.play_album(&list.albums[n]);
                 } else {
-                    println!("please enter a number between 0 and {}", albums.len());
+                    println!("please enter a number between 0 and {}", list.albums.len());
                 }
             } else {
                 let c = match self.album_handlers.iter().find(|h| h.is_match(cmd)) {
@@ -318,14 +609,111 @@ impl Controller {
                     }
                 };
 
+                let albums = &list.albums;
                 let should_return = match c {
-                    AlbumCmd::Play => self.album_cmd_play(&albums, arg)?,
+                    AlbumCmd::Play => self.album_cmd_play(albums, arg)?,
                     AlbumCmd::Help => {
                         self.album_cmd_help(arg);
                         false
                     }
-                    AlbumCmd::Queue => self.album_cmd_queue(&albums, arg)?,
-                    AlbumCmd::Save => self.album_cmd_save(&albums, arg)?,
+                    AlbumCmd::Queue => self.album_cmd_queue(albums, arg)?,
+                    AlbumCmd::Save => self.album_cmd_save(albums, arg)?,
+                    AlbumCmd::Unsave => self.album_cmd_unsave(albums, arg)?,
+                    AlbumCmd::Tracks => self.album_cmd_tracks(albums, arg)?,
+                    AlbumCmd::More => self.album_cmd_more(&mut list)?,
+                    AlbumCmd::Back => true,
+                };
+
+                if should_return {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    pub fn show_shell(&mut self, shows: Vec<SimplifiedShow>) -> SpotifyResult {
+        print_shows(&shows);
+
+        println!("type help for a list of available actions");
+        loop {
+            let input = crate::prompt(&self.shell_prompt());
+            if input.is_empty() {
+                println!("cancelled");
+                return Ok(());
+            }
+            let (cmd, arg) = crate::split_command(&input);
+            if arg.is_none() && crate::is_digits(cmd) {
+                let n = cmd.parse::<usize>().unwrap();
+                if n < shows.len() {
+                    return self.play_show(&shows[n]);
+                } else {
+                    println!("please enter a number between 0 and {}", shows.len());
+                }
+            } else {
+                let c = match self.show_handlers.iter().find(|h| h.is_match(cmd)) {
+                    Some(h) => h.cmd,
+                    None => {
+                        println!("{} is not a known command\ntype `help` for a list of available actions", cmd);
+                        continue;
+                    }
+                };
+
+                let should_return = match c {
+                    ShowCmd::Play => self.show_cmd_play(&shows, arg)?,
+                    ShowCmd::Help => {
+                        self.show_cmd_help(arg);
+                        false
+                    }
+                    ShowCmd::Episodes => self.show_cmd_episodes(&shows, arg)?,
+                    ShowCmd::Save => self.show_cmd_save(&shows, arg)?,
+                    ShowCmd::Unsave => self.show_cmd_unsave(&shows, arg)?,
+                    ShowCmd::Back => true,
+                };
+
+                if should_return {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    pub fn episode_shell(&mut self, episodes: Vec<SimplifiedEpisode>) -> SpotifyResult {
+        print_episodes(&episodes);
+
+        println!("type help for a list of available actions");
+        loop {
+            let input = crate::prompt(&self.shell_prompt());
+            if input.is_empty() {
+                println!("cancelled");
+                return Ok(());
+            }
+            let (cmd, arg) = crate::split_command(&input);
+            if arg.is_none() && crate::is_digits(cmd) {
+                let n = cmd.parse::<usize>().unwrap();
+                if n < episodes.len() {
+                    return self.play_episode(&episodes[n], false);
+                } else {
+                    println!("please enter a number between 0 and {}", episodes.len());
+                }
+            } else {
+                let c = match self.episode_handlers.iter().find(|h| h.is_match(cmd)) {
+                    Some(h) => h.cmd,
+                    None => {
+                        println!("{} is not a known command\ntype `help` for a list of available actions", cmd);
+                        continue;
+                    }
+                };
+
+                let should_return = match c {
+                    EpisodeCmd::Play => self.episode_cmd_play(&episodes, arg)?,
+                    EpisodeCmd::Help => {
+                        self.episode_cmd_help(arg);
+                        false
+                    }
+                    EpisodeCmd::Resume => self.episode_cmd_resume(&episodes, arg)?,
+                    EpisodeCmd::Queue => self.episode_cmd_queue(&episodes, arg)?,
+                    EpisodeCmd::Save => self.episode_cmd_save(&episodes, arg)?,
+                    EpisodeCmd::Back => true,
                 };
 
                 if should_return {
@@ -353,7 +741,7 @@ impl Controller {
 
         println!("type help for a list of available actions");
         loop {
-            let input = crate::prompt("command:");
+            let input = crate::prompt(&self.shell_prompt());
             if input.is_empty() {
                 println!("cancelled");
                 return Ok(());
@@ -382,6 +770,10 @@ impl Controller {
                         false
                     }
                     PlaylistCmd::Follow => self.playlist_cmd_follow(&pls, arg)?,
+                    PlaylistCmd::Unfollow => self.playlist_cmd_unfollow(&pls, arg)?,
+                    PlaylistCmd::Tracks => self.playlist_cmd_tracks(&pls, arg)?,
+                    PlaylistCmd::Clone => self.playlist_cmd_clone(&pls, arg)?,
+                    PlaylistCmd::Back => true,
                 };
 
                 if should_return {
@@ -1,4 +1,4 @@
-use super::Controller;
+use super::{AlbumList, Controller, TrackList};
 use crate::command::AlbumCmd;
 use rspotify::model::album::SimplifiedAlbum;
 
@@ -31,7 +31,7 @@ impl Controller {
     }
 
     pub fn album_cmd_queue(
-        &self,
+        &mut self,
         albums: &[SimplifiedAlbum],
         arg: Option<&str>,
     ) -> Result<bool, failure::Error> {
@@ -43,7 +43,7 @@ impl Controller {
             }
         };
 
-        let _n = match arg.parse::<usize>() {
+        let n = match arg.parse::<usize>() {
             Ok(n) if n >= albums.len() => {
                 println!("please enter a number between 0 and {}", albums.len());
                 return Ok(false);
@@ -55,8 +55,115 @@ impl Controller {
             }
         };
 
-        // TODO: fetch all the tracks and queue them all
-        todo!()
+        let tracks = self.album_tracks(&albums[n])?;
+        for t in &tracks {
+            self.queue(t.uri.clone())?;
+        }
+        println!(
+            "added {} tracks from {} to the queue",
+            tracks.len(),
+            &albums[n].name
+        );
+        Ok(true)
+    }
+
+    pub fn album_cmd_unsave(
+        &self,
+        albums: &[SimplifiedAlbum],
+        arg: Option<&str>,
+    ) -> Result<bool, failure::Error> {
+        let arg = match arg {
+            Some(a) => a,
+            None => {
+                self.show_album_usage(AlbumCmd::Unsave);
+                return Ok(false);
+            }
+        };
+
+        let n = match arg.parse::<usize>() {
+            Ok(n) if n >= albums.len() => {
+                println!("please enter a number between 0 and {}", albums.len());
+                return Ok(false);
+            }
+            Ok(n) => n,
+            Err(_) => {
+                self.show_album_usage(AlbumCmd::Unsave);
+                return Ok(false);
+            }
+        };
+
+        self.unsave_album(&albums[n]).map(|_| false)
+    }
+
+    pub fn album_cmd_tracks(
+        &mut self,
+        albums: &[SimplifiedAlbum],
+        arg: Option<&str>,
+    ) -> Result<bool, failure::Error> {
+        let arg = match arg {
+            Some(a) => a,
+            None => {
+                self.show_album_usage(AlbumCmd::Tracks);
+                return Ok(false);
+            }
+        };
+
+        let n = match arg.parse::<usize>() {
+            Ok(n) if n >= albums.len() => {
+                println!("please enter a number between 0 and {}", albums.len());
+                return Ok(false);
+            }
+            Ok(n) => n,
+            Err(_) => {
+                self.show_album_usage(AlbumCmd::Tracks);
+                return Ok(false);
+            }
+        };
+
+        let alb = &albums[n];
+        let tracks = self.album_tracks(alb)?;
+        if tracks.is_empty() {
+            println!("{} has no tracks", &alb.name);
+            return Ok(false);
+        }
+        // the shell needs full tracks
+        let full = crate::api::full_tracks(&self.client, &tracks)?;
+
+        let total: u32 = full.iter().map(|t| t.duration_ms).sum();
+        println!(
+            "{} by {}, {} tracks ({})",
+            &alb.name,
+            crate::join_artists(&alb.artists),
+            full.len(),
+            crate::format_duration(total)
+        );
+        let list = TrackList {
+            context: alb.uri.clone(),
+            album_order: true,
+            ..TrackList::from(full)
+        };
+        self.nested(alb.name.clone(), |c| c.track_list_shell(list))
+            .map(|_| false)
+    }
+
+    pub fn album_cmd_more(&self, list: &mut AlbumList) -> Result<bool, failure::Error> {
+        let next_page = match list.next_page.as_mut() {
+            Some(f) => f,
+            None => {
+                println!("there are no more albums");
+                return Ok(false);
+            }
+        };
+
+        let albums = next_page(&self.client)?;
+        if albums.is_empty() {
+            println!("there are no more albums");
+            list.next_page = None;
+        } else {
+            super::print_albums(&albums, list.albums.len());
+            list.albums.extend(albums);
+        }
+        Ok(false)
     }
 
     pub fn album_cmd_save(
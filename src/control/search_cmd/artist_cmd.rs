@@ -1,6 +1,9 @@
-use super::Controller;
+use super::{parse_index, AlbumList, ArtistList, Controller, NextPage};
 use crate::command::ArtistCmd;
-use rspotify::model::artist::FullArtist;
+use rspotify::{
+    model::{album::SimplifiedAlbum, artist::FullArtist},
+    senum::AlbumType,
+};
 
 impl Controller {
     pub fn artist_cmd_play(
@@ -59,6 +62,158 @@ impl Controller {
         self.follow_artist(&artists[n]).map(|_| true)
     }
 
+    pub fn artist_cmd_unfollow(
+        &self,
+        artists: &[FullArtist],
+        arg: Option<&str>,
+    ) -> Result<bool, failure::Error> {
+        match parse_index(artists.len(), arg) {
+            Some(n) => self.unfollow_artist(&artists[n]).map(|_| false),
+            None => {
+                self.show_artist_usage(ArtistCmd::Unfollow);
+                Ok(false)
+            }
+        }
+    }
+
+    pub fn artist_cmd_top(
+        &mut self,
+        artists: &[FullArtist],
+        arg: Option<&str>,
+    ) -> Result<bool, failure::Error> {
+        let n = match parse_index(artists.len(), arg) {
+            Some(n) => n,
+            None => {
+                self.show_artist_usage(ArtistCmd::Top);
+                return Ok(false);
+            }
+        };
+
+        let tracks = self.client.artist_top_tracks(&artists[n].id, None)?.tracks;
+        if tracks.is_empty() {
+            println!("{} has no top tracks", &artists[n].name);
+            return Ok(false);
+        }
+        let label = format!("{} top tracks", &artists[n].name);
+        self.nested(label, |c| c.track_shell(tracks)).map(|_| false)
+    }
+
+    pub fn artist_cmd_albums(
+        &mut self,
+        artists: &[FullArtist],
+        arg: Option<&str>,
+    ) -> Result<bool, failure::Error> {
+        let mut words = arg.unwrap_or_default().split_whitespace();
+        let index = words.next();
+        // `None` lists every kind of release in a single query
+        let types: Vec<Option<AlbumType>> = match (words.next(), words.next(), words.next()) {
+            (None, _, _) => vec![None],
+            (Some("--type"), Some(types), None) => {
+                match types
+                    .split(',')
+                    .map(|t| t.trim().parse::<AlbumType>().map(Some).map_err(|_| t))
+                    .collect::<Result<Vec<_>, _>>()
+                {
+                    Ok(types) => types,
+                    Err(t) => {
+                        println!(
+                            "{} is not an album type\navailable types are album, single, compilation and appears_on",
+                            t
+                        );
+                        return Ok(false);
+                    }
+                }
+            }
+            _ => {
+                self.show_artist_usage(ArtistCmd::Albums);
+                return Ok(false);
+            }
+        };
+        let n = match parse_index(artists.len(), index) {
+            Some(n) => n,
+            None => {
+                self.show_artist_usage(ArtistCmd::Albums);
+                return Ok(false);
+            }
+        };
+
+        // page through each requested type in turn
+        let id = artists[n].id.clone();
+        let mut types = types.into_iter();
+        let mut current = types.next();
+        let mut offset = 0;
+        let mut next_page: NextPage<SimplifiedAlbum> = Box::new(move |client| {
+            while let Some(album_type) = current {
+                let page = client.artist_albums(&id, album_type, None, Some(50), Some(offset))?;
+                offset += page.items.len() as u32;
+                if page.next.is_none() {
+                    current = types.next();
+                    offset = 0;
+                }
+                if !page.items.is_empty() {
+                    return Ok(page.items);
+                }
+            }
+            Ok(Vec::new())
+        });
+
+        let albums = next_page(&self.client)?;
+        if albums.is_empty() {
+            println!("{} has no albums", &artists[n].name);
+            return Ok(false);
+        }
+        let label = format!("{} albums", &artists[n].name);
+        let list = AlbumList {
+            albums,
+            next_page: Some(next_page),
+        };
+        self.nested(label, |c| c.album_list_shell(list))
+            .map(|_| false)
+    }
+
+    pub fn artist_cmd_related(
+        &mut self,
+        artists: &[FullArtist],
+        arg: Option<&str>,
+    ) -> Result<bool, failure::Error> {
+        let n = match parse_index(artists.len(), arg) {
+            Some(n) => n,
+            None => {
+                self.show_artist_usage(ArtistCmd::Related);
+                return Ok(false);
+            }
+        };
+
+        let related = self.client.artist_related_artists(&artists[n].id)?.artists;
+        if related.is_empty() {
+            println!("no related artists found for {}", &artists[n].name);
+            return Ok(false);
+        }
+        let label = format!("artists like {}", &artists[n].name);
+        self.nested(label, |c| c.artist_shell(related))
+            .map(|_| false)
+    }
+
+    pub fn artist_cmd_more(&self, list: &mut ArtistList) -> Result<bool, failure::Error> {
+        let next_page = match list.next_page.as_mut() {
+            Some(f) => f,
+            None => {
+                println!("there are no more artists");
+                return Ok(false);
+            }
+        };
+
+        let artists = next_page(&self.client)?;
+        if artists.is_empty() {
+            println!("there are no more artists");
+            list.next_page = None;
+        } else {
+            super::print_artists(&artists, list.artists.len());
+            list.artists.extend(artists);
+        }
+        Ok(false)
+    }
+
     pub fn artist_cmd_help(&self, arg: Option<&str>) {
         if let Some(a) = arg {
             if let Some(h) = self.artist_handlers.iter().find(|h| h.is_match(a)) {
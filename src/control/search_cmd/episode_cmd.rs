@@ -0,0 +1,89 @@
+use super::{parse_index, Controller};
+use crate::command::EpisodeCmd;
+use rspotify::model::show::SimplifiedEpisode;
+
+impl Controller {
+    pub fn episode_cmd_play(
+        &mut self,
+        episodes: &[SimplifiedEpisode],
+        arg: Option<&str>,
+    ) -> Result<bool, failure::Error> {
+        match parse_index(episodes.len(), arg) {
+            Some(n) => self.play_episode(&episodes[n], false).map(|_| true),
+            None => {
+                self.show_episode_usage(EpisodeCmd::Play);
+                Ok(false)
+            }
+        }
+    }
+
+    pub fn episode_cmd_resume(
+        &mut self,
+        episodes: &[SimplifiedEpisode],
+        arg: Option<&str>,
+    ) -> Result<bool, failure::Error> {
+        match parse_index(episodes.len(), arg) {
+            Some(n) => self.play_episode(&episodes[n], true).map(|_| true),
+            None => {
+                self.show_episode_usage(EpisodeCmd::Resume);
+                Ok(false)
+            }
+        }
+    }
+
+    pub fn episode_cmd_queue(
+        &mut self,
+        episodes: &[SimplifiedEpisode],
+        arg: Option<&str>,
+    ) -> Result<bool, failure::Error> {
+        let n = match parse_index(episodes.len(), arg) {
+            Some(n) => n,
+            None => {
+                self.show_episode_usage(EpisodeCmd::Queue);
+                return Ok(false);
+            }
+        };
+        self.queue(episodes[n].uri.clone()).map(|_| {
+            println!("added {} to the queue", &episodes[n].name);
+            true
+        })
+    }
+
+    pub fn episode_cmd_save(
+        &self,
+        episodes: &[SimplifiedEpisode],
+        arg: Option<&str>,
+    ) -> Result<bool, failure::Error> {
+        match parse_index(episodes.len(), arg) {
+            Some(n) => self
+                .save_episode(&episodes[n].id, &episodes[n].name)
+                .map(|_| false),
+            None => {
+                self.show_episode_usage(EpisodeCmd::Save);
+                Ok(false)
+            }
+        }
+    }
+
+    pub fn episode_cmd_help(&self, arg: Option<&str>) {
+        if let Some(a) = arg {
+            if let Some(h) = self.episode_handlers.iter().find(|h| h.is_match(a)) {
+                h.show_help();
+            } else {
+                println!("{} is not a known command or alias\nrun `help` for a list of available commands", a);
+            }
+        } else {
+            for h in &self.episode_handlers {
+                h.show_short_help();
+            }
+        }
+    }
+
+    pub fn show_episode_usage(&self, cmd: EpisodeCmd) {
+        for h in &self.episode_handlers {
+            if h.cmd == cmd {
+                h.show_usage();
+            }
+        }
+    }
+}
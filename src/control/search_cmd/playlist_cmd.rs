@@ -1,4 +1,4 @@
-use super::Controller;
+use super::{parse_index, Controller, NextPage, Noted, TrackList};
 use crate::command::PlaylistCmd;
 use crate::playlist::Playlist;
 
@@ -8,26 +8,37 @@ impl Controller {
         pls: &[Playlist],
         arg: Option<&str>,
     ) -> Result<bool, failure::Error> {
-        let arg = match arg {
-            Some(a) => a,
-            None => {
+        let words = arg
+            .unwrap_or_default()
+            .split_whitespace()
+            .collect::<Vec<_>>();
+        let (n, from) = match words[..] {
+            [n] => (n, None),
+            [n, "from", k] => match k.parse::<u32>() {
+                Ok(k) => (n, Some(k)),
+                Err(_) => {
+                    self.show_playlist_usage(PlaylistCmd::Play);
+                    return Ok(false);
+                }
+            },
+            _ => {
                 self.show_playlist_usage(PlaylistCmd::Play);
                 return Ok(false);
             }
         };
-        let n = match arg.parse::<usize>() {
-            Ok(n) if n >= pls.len() => {
-                println!("please enter a value between 0 and {}", pls.len());
-                return Ok(false);
-            }
-            Ok(n) => n,
-            Err(_) => {
+        let n = match parse_index(pls.len(), Some(n)) {
+            Some(n) => n,
+            None => {
                 self.show_playlist_usage(PlaylistCmd::Play);
                 return Ok(false);
             }
         };
 
-        self.play_playlist(&pls[n]).map(|_| true)
+        match from {
+            None => self.play_playlist(&pls[n]),
+            Some(k) => self.play_playlist_from(&pls[n], k),
+        }
+        .map(|_| true)
     }
 
     pub fn playlist_cmd_follow(
@@ -58,6 +69,97 @@ impl Controller {
         self.follow_playlist(&pls[n]).map(|_| true)
     }
 
+    pub fn playlist_cmd_unfollow(
+        &mut self,
+        pls: &[Playlist],
+        arg: Option<&str>,
+    ) -> Result<bool, failure::Error> {
+        match parse_index(pls.len(), arg) {
+            Some(n) => self.unfollow_playlist(&pls[n]).map(|_| false),
+            None => {
+                self.show_playlist_usage(PlaylistCmd::Unfollow);
+                Ok(false)
+            }
+        }
+    }
+
+    pub fn playlist_cmd_tracks(
+        &mut self,
+        pls: &[Playlist],
+        arg: Option<&str>,
+    ) -> Result<bool, failure::Error> {
+        let pl = match parse_index(pls.len(), arg) {
+            Some(n) => pls[n].clone(),
+            None => {
+                self.show_playlist_usage(PlaylistCmd::Tracks);
+                return Ok(false);
+            }
+        };
+
+        // the offset counts every item, including the ones that are skipped
+        let user = self.user.clone();
+        let id = pl.id().to_string();
+        let mut offset = 0;
+        let mut done = false;
+        let mut next_page: NextPage<Noted> = Box::new(move |client| {
+            while !done {
+                let page = client.user_playlist_tracks(&user, &id, None, 100, offset, None)?;
+                offset += page.items.len() as u32;
+                done = page.next.is_none() || page.items.is_empty();
+                let tracks = page
+                    .items
+                    .into_iter()
+                    .filter(|item| !item.is_local)
+                    .filter_map(|item| item.track.map(|t| (t, None)))
+                    .collect::<Vec<_>>();
+                if !tracks.is_empty() {
+                    return Ok(tracks);
+                }
+            }
+            Ok(Vec::new())
+        });
+
+        let tracks = next_page(&self.client)?;
+        if tracks.is_empty() {
+            println!("{} has no playable tracks", pl.name());
+            return Ok(false);
+        }
+        let list = TrackList {
+            context: Some(pl.uri().to_string()),
+            next_page: Some(next_page),
+            ..TrackList::from(tracks)
+        };
+        self.nested(pl.name().to_string(), |c| c.track_list_shell(list))
+            .map(|_| false)
+    }
+
+    pub fn playlist_cmd_clone(
+        &mut self,
+        pls: &[Playlist],
+        arg: Option<&str>,
+    ) -> Result<bool, failure::Error> {
+        let (n, name) = match arg.map(crate::split_command) {
+            Some((n, name)) => (Some(n), name),
+            None => (None, None),
+        };
+        let pl = match parse_index(pls.len(), n) {
+            Some(n) => &pls[n],
+            None => {
+                self.show_playlist_usage(PlaylistCmd::Clone);
+                return Ok(false);
+            }
+        };
+
+        let tracks = self.playable_tracks(pl)?;
+        if tracks.is_empty() {
+            println!("{} has no playable tracks", pl.name());
+            return Ok(false);
+        }
+        let name = name.unwrap_or_else(|| pl.name()).to_string();
+        self.write_playlist(None, &name, tracks, false)
+            .map(|_| false)
+    }
+
     pub fn playlist_cmd_help(&self, arg: Option<&str>) {
         if let Some(a) = arg {
             if let Some(h) = self.playlist_handlers.iter().find(|h| h.is_match(a)) {
@@ -0,0 +1,93 @@
+use super::{parse_index, Controller};
+use crate::command::ShowCmd;
+use rspotify::model::show::SimplifiedShow;
+
+impl Controller {
+    pub fn show_cmd_play(
+        &mut self,
+        shows: &[SimplifiedShow],
+        arg: Option<&str>,
+    ) -> Result<bool, failure::Error> {
+        match parse_index(shows.len(), arg) {
+            Some(n) => self.play_show(&shows[n]).map(|_| true),
+            None => {
+                self.show_show_usage(ShowCmd::Play);
+                Ok(false)
+            }
+        }
+    }
+
+    pub fn show_cmd_episodes(
+        &mut self,
+        shows: &[SimplifiedShow],
+        arg: Option<&str>,
+    ) -> Result<bool, failure::Error> {
+        match parse_index(shows.len(), arg) {
+            Some(n) => self.show_episodes(&shows[n]).map(|_| false),
+            None => {
+                self.show_show_usage(ShowCmd::Episodes);
+                Ok(false)
+            }
+        }
+    }
+
+    pub fn show_cmd_save(
+        &self,
+        shows: &[SimplifiedShow],
+        arg: Option<&str>,
+    ) -> Result<bool, failure::Error> {
+        let n = match parse_index(shows.len(), arg) {
+            Some(n) => n,
+            None => {
+                self.show_show_usage(ShowCmd::Save);
+                return Ok(false);
+            }
+        };
+        self.client.save_shows(vec![shows[n].id.clone()]).map(|_| {
+            println!("saved {} to your library", &shows[n].name);
+            false
+        })
+    }
+
+    pub fn show_cmd_unsave(
+        &self,
+        shows: &[SimplifiedShow],
+        arg: Option<&str>,
+    ) -> Result<bool, failure::Error> {
+        let n = match parse_index(shows.len(), arg) {
+            Some(n) => n,
+            None => {
+                self.show_show_usage(ShowCmd::Unsave);
+                return Ok(false);
+            }
+        };
+        self.client
+            .remove_users_saved_shows(vec![shows[n].id.clone()], None)
+            .map(|_| {
+                println!("removed {} from your library", &shows[n].name);
+                false
+            })
+    }
+
+    pub fn show_cmd_help(&self, arg: Option<&str>) {
+        if let Some(a) = arg {
+            if let Some(h) = self.show_handlers.iter().find(|h| h.is_match(a)) {
+                h.show_help();
+            } else {
+                println!("{} is not a known command or alias\nrun `help` for a list of available commands", a);
+            }
+        } else {
+            for h in &self.show_handlers {
+                h.show_short_help();
+            }
+        }
+    }
+
+    pub fn show_show_usage(&self, cmd: ShowCmd) {
+        for h in &self.show_handlers {
+            if h.cmd == cmd {
+                h.show_usage();
+            }
+        }
+    }
+}
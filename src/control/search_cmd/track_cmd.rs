@@ -1,13 +1,15 @@
-use super::Controller;
-use crate::command::TrackCmd;
-use rspotify::model::track::FullTrack;
+use super::{Controller, TrackList};
+use crate::{api, command::TrackCmd, smart::Condition, SpotifyResult};
+use rspotify::model::{offset, track::FullTrack, PlayingItem};
+use std::mem;
 
 impl Controller {
     pub fn track_cmd_play(
         &mut self,
-        tracks: &[FullTrack],
+        list: &TrackList,
         arg: Option<&str>,
     ) -> Result<bool, failure::Error> {
+        let tracks = &list.tracks;
         let arg = match arg {
             Some(a) => a,
             None => {
@@ -27,11 +29,70 @@ impl Controller {
             }
         };
 
-        self.play_track(&tracks[n]).map(|_| true)
+        self.play_from_list(list, n).map(|_| true)
+    }
+
+    pub fn play_from_list(&mut self, list: &TrackList, n: usize) -> SpotifyResult {
+        let track = &list.tracks[n];
+        if let Some(positions) = list.queue_positions.as_ref() {
+            // the queue may have moved on since it was listed
+            let queue = api::queue(&self.client)?.queue;
+            let is_track = |i: &usize| match queue.get(*i) {
+                Some(PlayingItem::Track(t)) => t.uri == track.uri,
+                _ => false,
+            };
+            let pos = match Some(positions[n])
+                .filter(is_track)
+                .or_else(|| (0..queue.len()).find(is_track))
+            {
+                Some(i) => i,
+                None => {
+                    println!("{} is no longer in the queue", &track.name);
+                    return Ok(());
+                }
+            };
+            for _ in 0..=pos {
+                self.on_device(|c, dev| c.next_track(dev))?;
+            }
+            self.playing = true;
+            println!(
+                "skipped to {} by {}",
+                &track.name,
+                crate::join_artists(&track.artists)
+            );
+            return Ok(());
+        }
+        match list.context.as_ref() {
+            None => self.play_track(track),
+            Some(ctx) => self
+                .on_device(|c, dev| {
+                    c.start_playback(
+                        dev,
+                        Some(ctx.clone()),
+                        None,
+                        // filtering drops tracks, so `n` is no longer the position in the album
+                        if list.album_order && list.filters.is_empty() {
+                            offset::for_position(n as u32)
+                        } else {
+                            offset::for_uri(track.uri.clone())
+                        },
+                        None,
+                    )
+                })
+                .map(|_| {
+                    self.playing = true;
+                    println!(
+                        "playing {} [{}] by {}",
+                        &track.name,
+                        &track.album.name,
+                        crate::join_artists(&track.artists)
+                    );
+                }),
+        }
     }
 
     pub fn track_cmd_queue(
-        &self,
+        &mut self,
         tracks: &[FullTrack],
         arg: Option<&str>,
     ) -> Result<bool, failure::Error> {
@@ -89,6 +150,66 @@ impl Controller {
         self.like_track(&tracks[n]).map(|_| true)
     }
 
+    pub fn track_cmd_unlike(
+        &self,
+        tracks: &[FullTrack],
+        arg: Option<&str>,
+    ) -> Result<bool, failure::Error> {
+        let arg = match arg {
+            Some(a) => a,
+            None => {
+                self.show_track_usage(TrackCmd::Unlike);
+                return Ok(false);
+            }
+        };
+
+        let n = match arg.parse::<usize>() {
+            Ok(n) if n >= tracks.len() => {
+                println!("please enter a number between 0 and {}", tracks.len());
+                return Ok(false);
+            }
+            Ok(n) => n,
+            Err(_) => {
+                self.show_track_usage(TrackCmd::Unlike);
+                return Ok(false);
+            }
+        };
+
+        self.unlike_track(&tracks[n]).map(|_| false)
+    }
+
+    pub fn track_cmd_more(&self, list: &mut TrackList) -> Result<bool, failure::Error> {
+        let next_page = match list.next_page.as_mut() {
+            Some(f) => f,
+            None => {
+                println!("there are no more tracks");
+                return Ok(false);
+            }
+        };
+
+        let mut entries = next_page(&self.client)?;
+        if entries.is_empty() {
+            println!("there are no more tracks");
+            list.next_page = None;
+            return Ok(false);
+        }
+
+        if !list.filters.is_empty() {
+            let tracks = entries.iter().map(|(t, _)| t.clone()).collect::<Vec<_>>();
+            let mut keep = self.filter_mask(&tracks, &list.filters)?.into_iter();
+            entries.retain(|_| keep.next().unwrap_or(false));
+        }
+        if entries.is_empty() {
+            println!("none of the tracks on the next page match the filters");
+        } else {
+            let (tracks, notes): (Vec<_>, Vec<_>) = entries.into_iter().unzip();
+            super::print_tracks(&tracks, &notes, list.tracks.len(), list.album_order);
+            list.tracks.extend(tracks);
+            list.notes.extend(notes);
+        }
+        Ok(false)
+    }
+
     pub fn track_cmd_help(&self, arg: Option<&str>) {
         if let Some(a) = arg {
             if let Some(h) = self.track_handlers.iter().find(|h| h.is_match(a)) {
@@ -132,6 +253,92 @@ impl Controller {
         self.save_track(tracks[n].clone(), arg).map(|_| true)
     }
 
+    pub fn track_cmd_queue_all(&mut self, tracks: &[FullTrack]) -> Result<bool, failure::Error> {
+        for t in tracks {
+            self.queue(t.uri.clone())?;
+        }
+        println!("added {} tracks to the queue", tracks.len());
+        Ok(false)
+    }
+
+    pub fn track_cmd_save_all(
+        &mut self,
+        tracks: &[FullTrack],
+        arg: Option<&str>,
+    ) -> Result<bool, failure::Error> {
+        match self.choose_user_playlist(arg)? {
+            None => {
+                println!("cancelled");
+                Ok(false)
+            }
+            Some(pl) => {
+                let name = pl.name().to_string();
+                self.write_playlist(Some(pl), &name, tracks.to_vec(), true)
+                    .map(|_| false)
+            }
+        }
+    }
+
+    pub fn track_cmd_features(
+        &self,
+        tracks: &[FullTrack],
+        arg: Option<&str>,
+    ) -> Result<bool, failure::Error> {
+        match arg.map(str::parse::<usize>) {
+            Some(Ok(n)) if n >= tracks.len() => {
+                println!("please enter a number between 0 and {}", tracks.len());
+                Ok(false)
+            }
+            Some(Ok(n)) => self.show_features(&tracks[n]).map(|_| false),
+            _ => {
+                self.show_track_usage(TrackCmd::Features);
+                Ok(false)
+            }
+        }
+    }
+
+    pub fn track_cmd_filter(
+        &self,
+        list: &mut TrackList,
+        arg: Option<&str>,
+    ) -> Result<bool, failure::Error> {
+        let conds = match arg
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(str::parse::<Condition>)
+            .collect::<Result<Vec<_>, _>>()
+        {
+            Ok(v) if !v.is_empty() => v,
+            Ok(_) => {
+                self.show_track_usage(TrackCmd::Filter);
+                return Ok(false);
+            }
+            Err(e) => {
+                println!("{}", e);
+                return Ok(false);
+            }
+        };
+
+        let total = list.tracks.len();
+        let keep = self.filter_mask(&list.tracks, &conds)?;
+        if let Some(positions) = list.queue_positions.as_mut() {
+            let mut keep = keep.iter();
+            positions.retain(|_| *keep.next().unwrap_or(&false));
+        }
+        let (tracks, notes) = mem::take(&mut list.tracks)
+            .into_iter()
+            .zip(mem::take(&mut list.notes))
+            .zip(keep)
+            .filter_map(|(entry, keep)| if keep { Some(entry) } else { None })
+            .unzip();
+        list.tracks = tracks;
+        list.notes = notes;
+        list.filters.extend(conds);
+        println!("{} of {} tracks match", list.tracks.len(), total);
+        super::print_tracks(&list.tracks, &list.notes, 0, list.album_order);
+        Ok(false)
+    }
+
     pub fn show_track_usage(&self, cmd: TrackCmd) {
         for h in &self.track_handlers {
             if h.cmd == cmd {
@@ -0,0 +1,204 @@
+use super::Controller;
+use crate::{
+    command::Cmd,
+    playlist::{self, Playlist},
+    smart::SmartPlaylist,
+    split_command, SpotifyResult,
+};
+use std::collections::{HashMap, HashSet};
+
+impl Controller {
+    pub fn smart(&mut self, arg: Option<&str>) -> SpotifyResult {
+        let (sub, name) = match arg {
+            Some(a) => split_command(a),
+            None => ("list", None),
+        };
+
+        match &sub.to_lowercase()[..] {
+            "list" | "ls" => {
+                if self.config.smart_playlists.is_empty() {
+                    println!("there are no smart playlists defined in the config");
+                }
+                for sp in &self.config.smart_playlists {
+                    println!("{}", &sp.name);
+                }
+                Ok(())
+            }
+            "sync" => {
+                let defs = match name {
+                    Some(n) => match self
+                        .config
+                        .smart_playlists
+                        .iter()
+                        .find(|sp| crate::equalfold(&sp.name, n))
+                    {
+                        Some(sp) => vec![sp.clone()],
+                        None => {
+                            println!("there is no smart playlist named {}", n);
+                            return Ok(());
+                        }
+                    },
+                    None => self.config.smart_playlists.clone(),
+                };
+                if defs.is_empty() {
+                    println!("there are no smart playlists defined in the config");
+                }
+                for sp in &defs {
+                    self.sync_smart_playlist(sp)?;
+                }
+                Ok(())
+            }
+            _ => {
+                self.show_usage(Cmd::Smart);
+                Ok(())
+            }
+        }
+    }
+
+    fn sync_smart_playlist(&mut self, sp: &SmartPlaylist) -> SpotifyResult {
+        if let Err(e) = sp.validate() {
+            println!("{}", e);
+            return Ok(());
+        }
+
+        let pls = self.get_playlists()?;
+        let find = |name: &str| pls.iter().find(|p| p.name_eq(name)).cloned();
+        let target = pls
+            .iter()
+            .find(|p| p.owner().id == self.user && p.name_eq(&sp.name))
+            .cloned();
+
+        let sources = if sp.in_playlists.is_empty() {
+            pls.iter()
+                .filter(|p| p.owner().id == self.user && !p.name_eq(&sp.name))
+                .cloned()
+                .collect::<Vec<_>>()
+        } else {
+            let mut v = Vec::new();
+            for name in &sp.in_playlists {
+                match find(name) {
+                    Some(p) => v.push(p),
+                    None => {
+                        println!(
+                            "{}: you don't seem to have a playlist named {}",
+                            &sp.name, name
+                        );
+                        return Ok(());
+                    }
+                }
+            }
+            v
+        };
+
+        let mut excluded = HashSet::new();
+        for name in &sp.not_in {
+            let pl = match find(name) {
+                Some(p) => p,
+                None => {
+                    println!(
+                        "{}: you don't seem to have a playlist named {}",
+                        &sp.name, name
+                    );
+                    return Ok(());
+                }
+            };
+            for item in pl.all_tracks(&self.client, &self.user)? {
+                if let Some(t) = item.track.as_ref() {
+                    excluded.insert(playlist::track_key(t).to_string());
+                }
+            }
+        }
+
+        let mut seen = HashSet::new();
+        let mut items = Vec::new();
+        for pl in &sources {
+            for item in pl.all_tracks(&self.client, &self.user)? {
+                if item.is_local {
+                    continue;
+                }
+                if let Some(t) = item.track.as_ref() {
+                    let key = playlist::track_key(t).to_string();
+                    if !excluded.contains(&key) && seen.insert(key) {
+                        items.push(item);
+                    }
+                }
+            }
+        }
+
+        let mut genres = HashMap::<String, Vec<String>>::new();
+        if sp.needs_genres() {
+            let ids = items
+                .iter()
+                .flat_map(|item| item.track.as_ref().unwrap().artists.iter())
+                .filter_map(|a| a.id.clone())
+                .collect::<HashSet<_>>()
+                .into_iter()
+                .collect::<Vec<_>>();
+            for chunk in ids.chunks(50) {
+                for a in self.client.artists(chunk.to_vec())?.artists {
+                    genres.insert(a.id, a.genres);
+                }
+            }
+        }
+
+        let features = if sp.needs_features() {
+            let ids = items
+                .iter()
+                .filter_map(|item| item.track.as_ref().unwrap().id.clone())
+                .collect::<Vec<_>>();
+            self.audio_features(&ids)?
+        } else {
+            HashMap::new()
+        };
+
+        let mut tracks = items
+            .iter()
+            .filter(|item| {
+                let t = item.track.as_ref().unwrap();
+                let track_genres = t
+                    .artists
+                    .iter()
+                    .filter_map(|a| a.id.as_ref().and_then(|id| genres.get(id)))
+                    .flatten()
+                    .map(|g| &g[..])
+                    .collect::<Vec<_>>();
+                sp.matches(
+                    item,
+                    &track_genres,
+                    t.id.as_ref().and_then(|id| features.get(id)),
+                )
+            })
+            .map(|item| item.track.clone().unwrap())
+            .collect::<Vec<_>>();
+        if let Some(n) = sp.max_length {
+            tracks.truncate(n);
+        }
+
+        let pl = match target {
+            Some(p) => p,
+            None => {
+                let pl = Playlist::from(
+                    self.client.user_playlist_create(
+                        &self.user,
+                        &sp.name,
+                        None,
+                        sp.description
+                            .clone()
+                            .unwrap_or_else(|| String::from("smart playlist managed by libman")),
+                    )?,
+                );
+                println!("created playlist {}", pl.name());
+                if let Some(v) = self.pl_cache.as_mut() {
+                    v.insert(0, pl.clone());
+                }
+                pl
+            }
+        };
+
+        self.replace_tracks(pl.id(), &tracks)?;
+        println!("synced {}: {} tracks", pl.name(), tracks.len());
+        let pl = self.fetch_playlist(pl.id())?;
+        self.replace_cached(pl);
+        Ok(())
+    }
+}
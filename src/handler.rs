@@ -1,4 +1,4 @@
-use crate::command::{AlbumCmd, ArtistCmd, Cmd, PlaylistCmd, TrackCmd};
+use crate::command::{AlbumCmd, ArtistCmd, Cmd, EpisodeCmd, PlaylistCmd, ShowCmd, TrackCmd};
 use std::borrow::Cow;
 
 pub struct Handler<T> {
@@ -131,6 +131,22 @@ pub fn default_handlers() -> Vec<Handler<Cmd>> {
 	&["spla"],
 	"Search for a public playlist.",
 	),
+	new(
+	SearchShow,
+	"search-show",
+	"Search for a podcast.",
+	"search-show <show>",
+	&["ssho"],
+	"Search for a podcast show.\nFrom the results you can play, save or unsave a show, or see its episodes.",
+	),
+	new(
+	SearchEpisode,
+	"search-episode",
+	"Search for a podcast episode.",
+	"search-episode <episode>",
+	&["sepi"],
+	"Search for a podcast episode.\nFrom the results you can play, resume, queue or save an episode.",
+	),
 	// play-first commands
 	new(
 	PlayFirstTrack,
@@ -164,8 +180,25 @@ pub fn default_handlers() -> Vec<Handler<Cmd>> {
 	&["ppla"],
 	"Search for a public playlist and play the first result.",
 	),
+	new(
+	PlayFirstEpisode,
+	"play-episode",
+	"Search for a podcast episode and play the first result.",
+	"play-episode <episode>",
+	&["pepi"],
+	"Search for a podcast episode and play the first result from the beginning.\nUse `resume` to continue where you left off instead.",
+	),
 	// player commands
 	new(
+	ShowQueue,
+	"queue",
+	"Show the playing queue.",
+	"queue",
+	&["upcoming", "up"],
+	"Show the currently playing item and the tracks that will play after it.
+Enter a tracks number to skip ahead to it. Tracks you queued with libman are marked.",
+	),
+	new(
 	SetVolume,
 	"volume",
 	"Change the volume.",
@@ -204,6 +237,72 @@ pub fn default_handlers() -> Vec<Handler<Cmd>> {
 	"prev",
 	&["<"],
 	"Plays the previous track.",
+	),
+	new(
+	Seek,
+	"seek",
+	"Jump to a position in the playing track.",
+	"seek <position>",
+	&[],
+	"Jump to a position in the playing track.
+The position can be a time like `1:23` or `90`, a percentage like `50%`,
+or relative to the current position like `+30`, `-10s` or `+1m`.",
+	),
+	new(
+	SeekForward,
+	"forward",
+	"Skip forward in the playing track.",
+	"forward [seconds]",
+	&[">>"],
+	"Skip forward 10 seconds, or the given number of seconds, in the playing track.",
+	),
+	new(
+	SeekBackward,
+	"rewind",
+	"Skip back in the playing track.",
+	"rewind [seconds]",
+	&["<<"],
+	"Skip back 10 seconds, or the given number of seconds, in the playing track.",
+	),
+	new(
+	Resume,
+	"resume",
+	"Continue a podcast episode where you left off.",
+	"resume <episode>",
+	&[],
+	"Search for a podcast episode and play the first result from where you stopped listening to it.
+Episodes you finished or never started play from the beginning.",
+	),
+	new(
+	Sleep,
+	"sleep",
+	"Pause the playback later.",
+	"sleep <time|end-of-track|end-of-album|cancel> [--fade]",
+	&["timer"],
+	"Start a sleep timer that pauses the playback when it runs out.
+	time: How long to wait, for example 30m, 90s or 1:30:00.
+	end-of-track: Pause when the playing track ends.
+	end-of-album: Pause when the last track of the playing album ends.
+	cancel: Stop the running timer.
+	--fade: Lower the volume gradually during the last minute.
+Without arguments, shows how much time is left. The timer keeps running while you use other commands.",
+	),
+	new(
+	At,
+	"at",
+	"Schedule a command.",
+	"at [<HH:MM> [days] <command> [--device <name>] [--volume <N>] [--fade-in <time>] | remove <N>]",
+	&["alarm"],
+	"Run a command at a set time, like an alarm.
+	HH:MM: The time to run the command at.
+	days: daily, weekdays, weekends or a list like mon,wed,fri. Without it the command runs once.
+	command: Any command you could type in the prompt, like play Morning.
+	--device: Play on the given device.
+	--volume: Set the volume, in percent.
+	--fade-in: Start silent and raise the volume over the given time, like 5m.
+	remove N: Delete the schedule with the given number.
+Without arguments, lists the schedules.
+Schedules are saved to the file in the LIBMAN_SCHEDULE env variable (./libman_schedule.json by default), and they only run while libman is started with the --schedule flag.",
 	),
 	// library commands
 	new(
@@ -212,15 +311,163 @@ pub fn default_handlers() -> Vec<Handler<Cmd>> {
 	"Save the currently playing track to a playlist.",
 	"save-playing [playlist]",
 	&["save", "add"],
-	"Save the currently playing track to one of your playlists.\nWithout any argument, prompts you for a playlist, else adds to the given playlist.",
+	"Save the currently playing track to one of your playlists.\nWithout any argument, prompts you for a playlist, else adds to the given playlist.
+If a podcast episode is playing, it's saved to Your Episodes instead.",
 	),
 	new(
 	RemovePlaying,
 	"remove-playing",
 	"Remove the currently playing track from a playlist.",
-	"remove-playing [playlist]",
+	"remove-playing [playlist|--all]",
 	&["rm"],
-	"Remove the currently playing track from a playlist.\nIf no playlist name is given, the last played playlist will be assumed.",
+	"Remove the currently playing track from a playlist.\nIf no playlist name is given, the last played playlist will be assumed.
+With `--all`, the track is removed from every playlist of yours that contains it.",
+	),
+	new(
+	CopyTracks,
+	"copy",
+	"Copy tracks from one playlist to another.",
+	"copy <from> <to> [indices|filter]",
+	&["cp"],
+	"Copy tracks from one of your playlists to another.
+Names with spaces must be quoted.
+Tracks can be picked by their numbers, for example `0,3,7-9`, or by a text that their name, artist or album contains.
+Use `all` to copy every track. Without a selection, the tracks are listed and you will be prompted for one.
+Tracks that are already in the destination are skipped.",
+	),
+	new(
+	MoveTracks,
+	"move",
+	"Move tracks from one playlist to another.",
+	"move <from> <to> [indices|filter]",
+	&["mv"],
+	"Move tracks from one of your playlists to another.
+Works like `copy`, but the selected tracks are also removed from the source playlist.",
+	),
+	new(
+	Where,
+	"where",
+	"Find which of your playlists contain a track.",
+	"where [playing|uri|query]",
+	&["which"],
+	"List every playlist of yours that contains a track, with the positions of the track.
+Without an argument or with `playing`, the currently playing track is looked up.
+The argument can also be a track uri or url, or a search query like `track by artist`.",
+	),
+	new(
+	Smart,
+	"smart",
+	"List or regenerate smart playlists.",
+	"smart [list | sync [name]]",
+	&[],
+	"Smart playlists are defined in the config file with rules.
+	list (or empty): Show the smart playlists defined in the config.
+	sync: Regenerate the given smart playlist, or all of them if no name is given.
+If the playlist doesn't exist yet, it is created.",
+	),
+	new(
+	Liked,
+	"liked",
+	"Browse or play your liked songs.",
+	"liked [play]",
+	&["favs"],
+	"Without an argument, lists your liked songs so you can play, queue, unlike or save them.
+	play: Play your liked songs.",
+	),
+	new(
+	LikePlaying,
+	"like",
+	"Save the currently playing track to your liked songs.",
+	"like",
+	&["fav"],
+	"Save the currently playing track to your 'liked songs'.",
+	),
+	new(
+	UnlikePlaying,
+	"unlike",
+	"Remove the currently playing track from your liked songs.",
+	"unlike",
+	&["unfav"],
+	"Remove the currently playing track from your 'liked songs'.",
+	),
+	new(
+	SavedAlbums,
+	"albums",
+	"Browse the albums in your library.",
+	"albums [added|artist|release-date] [asc|desc]",
+	&["alb"],
+	"List the albums saved in your library so you can play, queue, unsave them or see their tracks.
+The list is sorted by the date the albums were added, newest first, unless another order is given.
+Sorting by artist or release date is ascending unless `desc` is given.",
+	),
+	new(
+	FollowedArtists,
+	"artists",
+	"Browse the artists you follow.",
+	"artists",
+	&["art"],
+	"List the artists you follow with their genres and follower counts.
+From there you can play or unfollow them, or see their top tracks, albums and related artists.",
+	),
+	new(
+	SavedShows,
+	"shows",
+	"Browse the podcasts in your library.",
+	"shows",
+	&["podcasts"],
+	"List the podcast shows saved in your library, newest first.
+From there you can play or unsave them, or see their episodes.",
+	),
+	new(
+	Recommend,
+	"recommend",
+	"Get track recommendations.",
+	"recommend [--seed playing|artist:X|track:Y|genre:Z] [--<feature> <value|min-max>] [--limit N]",
+	&["rec"],
+	"Get track recommendations from up to 5 seeds; the currently playing track is used if no seed is given.
+Seeds can be repeated, artists and tracks are searched by name. Quote names with spaces: --seed artist:\"Daft Punk\".
+Tunable attributes are given as a target value or a min-max range, for example `--energy 0.7 --tempo 120-130`.
+Available attributes: acousticness, danceability, duration_ms, energy, instrumentalness, key, liveness, loudness, mode, popularity, speechiness, tempo, time_signature, valence.
+The results open in a track shell where you can also queue or save all of them.",
+	),
+	new(
+	Features,
+	"features",
+	"Show the audio features of a track.",
+	"features [playing|track]",
+	&["feat"],
+	"Show the tempo, key, energy, danceability, valence, acousticness and loudness of a track.
+Without an argument the currently playing track is used, otherwise the track is searched.",
+	),
+	new(
+	History,
+	"history",
+	"Show your recently played tracks.",
+	"history [N]",
+	&["hist", "recent"],
+	"Show the last N (at most 50, 20 by default) tracks you played, when you played them and where from.
+They open in a track shell where you can replay, queue or save them; `more` goes further back in time.",
+	),
+	new(
+	Top,
+	"top",
+	"Show your top tracks or artists.",
+	"top <tracks|artists> [--range short|medium|long] [--limit N] [--save <playlist>]",
+	&[],
+	"Show the tracks or artists you listened to the most, ranked, and open them in a shell.
+The range is about 4 weeks for short, 6 months for medium (the default) and several years for long.
+The limit is 20 by default and at most 50.
+With --save, your top tracks are written into the playlist instead, replacing its contents; the playlist is created if you don't have it.",
+	),
+	new(
+	Stats,
+	"stats",
+	"Show statistics from your listening log.",
+	"stats [days]",
+	&[],
+	"Show your plays and skip rates per artist, per day and per playlist, from the listening log.
+With days, only the plays of the last that many days are counted.
+The listening log has to be turned on in the config, and it only records while libman is open.",
 	),
 	new(
 	CreatePlaylist,
@@ -246,6 +493,63 @@ pub fn default_handlers() -> Vec<Handler<Cmd>> {
 	&["delete"],
 	"Delete or unfollow one of your playlists.",
 	),
+	new(
+	SortPlaylist,
+	"sort",
+	"Permanently sort a playlist.",
+	"sort [playlist] by <artist|album|title|release-date|added|popularity|duration> [desc]",
+	&[],
+	"Permanently change the order of one of your playlists.
+Without a playlist name, you will be prompted to choose one.
+Only the tracks that are out of place are moved.",
+	),
+	new(
+	ShufflePlaylist,
+	"shuffle-permanent",
+	"Permanently shuffle a playlist.",
+	"shuffle-permanent [playlist]",
+	&["shp"],
+	"Write a random order to one of your playlists.\nWithout a playlist name, you will be prompted to choose one.",
+	),
+	new(
+	Merge,
+	"merge",
+	"Combine two playlists into another.",
+	"merge <A> <B> -> <C> [--new] [--append] [--keep-dupes]",
+	&[],
+	"Write the tracks of A followed by the tracks of B into C.
+Names with spaces must be quoted, for example `merge \"Road Trip\" Gym -> Mix`.
+If C is one of your playlists its contents are replaced, otherwise a new playlist is created.
+	--new: Always create a new playlist called C.
+	--append: Add the missing tracks to the end of C instead of replacing it.
+	--keep-dupes: Do not remove duplicate tracks from the result.",
+	),
+	new(
+	Intersect,
+	"intersect",
+	"Write the tracks two playlists have in common into another.",
+	"intersect <A> <B> -> <C> [--new] [--append] [--keep-dupes]",
+	&[],
+	"Write the tracks of A that are also in B into C.
+Names with spaces must be quoted.
+If C is one of your playlists its contents are replaced, otherwise a new playlist is created.
+	--new: Always create a new playlist called C.
+	--append: Add the missing tracks to the end of C instead of replacing it.
+	--keep-dupes: Do not remove duplicate tracks from the result.",
+	),
+	new(
+	Subtract,
+	"subtract",
+	"Write the tracks of a playlist that are not in another into a third one.",
+	"subtract <A> <B> -> <C> [--new] [--append] [--keep-dupes]",
+	&[],
+	"Write the tracks of A that are not in B into C.
+Names with spaces must be quoted, for example `subtract Gym Chill -> \"Gym minus Chill\"`.
+If C is one of your playlists its contents are replaced, otherwise a new playlist is created.
+	--new: Always create a new playlist called C.
+	--append: Add the missing tracks to the end of C instead of replacing it.
+	--keep-dupes: Do not remove duplicate tracks from the result.",
+	),
 	// misc commands
 	new(
 	Help,
@@ -267,9 +571,15 @@ pub fn default_handlers() -> Vec<Handler<Cmd>> {
 	SetDevice,
 	"device",
 	"Choose a playback device.",
-	"device [name]",
+	"device [name|alias] [--prefer] | --forget",
 	&["dev"],
-	"Choose a playback device.",
+	"Move the playback to a device.
+	name: The device name, case doesn't matter and the start of the name is enough if it's unique.
+	alias: A short name for a device, set in the config.
+	--prefer: Also remember the device as your preferred one.
+	--forget: Forget your preferred device.
+Without a name, lists your devices with their types and volumes, marking the active and preferred ones, and asks you to pick one.
+When a player request fails because no device is active, the playback is moved to your preferred device and the request is sent again.",
 	),
 	new(
 	Show,
@@ -279,10 +589,24 @@ pub fn default_handlers() -> Vec<Handler<Cmd>> {
 	&["sw"],
 	"Show various items.\n
 	lib|library: Show a list of your playlists.
-	playing (or empty): Show currently playing track.
+	playing (or empty): Show what's playing; the layout can be changed in the config.
 	playlist: Show one of your playlists by name.",
 	),
 	new(
+	Watch,
+	"watch",
+	"Follow the playback live.",
+	"watch [seconds]",
+	&["w"],
+	"Show what's playing and keep it updated every 2 seconds, or the given number of seconds, until a key is pressed.
+While watching you can use these keys:
+	space: pause or resume
+	n: next track
+	p: previous track
+	+ and -: change the volume
+	l: like the playing track",
+	),
+	new(
 	Prompt,
 	"prompt",
 	"Change the libman prompt.",
@@ -331,6 +655,66 @@ pub fn default_track_handlers() -> Vec<Handler<TrackCmd>> {
 	"Save a track to your 'liked songs'.",
 	),
 	new(
+	Unlike,
+	"unlike",
+	"Remove a track from your 'liked songs'.",
+	"unlike <N>",
+	&["unfav", "unfave"],
+	"Remove a track from your 'liked songs'.",
+	),
+	new(
+	QueueAll,
+	"queue-all",
+	"Add all the tracks to your playing queue.",
+	"queue-all",
+	&["qa"],
+	"Add all the listed tracks to your playing queue.",
+	),
+	new(
+	SaveAll,
+	"save-all",
+	"Save all the tracks to a playlist.",
+	"save-all [playlist]",
+	&["sa"],
+	"Add all the listed tracks to one of your playlists.
+Tracks that are already in the playlist are skipped.",
+	),
+	new(
+	Features,
+	"features",
+	"Show the audio features of a track.",
+	"features <N>",
+	&["feat"],
+	"Show the tempo, key, energy, danceability, valence, acousticness and loudness of a track.",
+	),
+	new(
+	Filter,
+	"filter",
+	"Narrow the tracks down by their audio features.",
+	"filter <condition...>",
+	&["f"],
+	"Keep only the tracks matching all the conditions, for example `filter tempo>120 energy>0.6`.
+Supported operators are >, <, >=, <= and =; tracks loaded with `more` are filtered too.
+= compares up to the digits given, so tempo=120 matches 119.5 to 120.5 and energy=0.8 matches 0.75 to 0.85.
+Available features: acousticness, danceability, energy, instrumentalness, liveness, loudness, speechiness, tempo, valence.",
+	),
+	new(
+	More,
+	"more",
+	"Load more tracks.",
+	"more",
+	&["m"],
+	"Load the next page of tracks, if there is one.",
+	),
+	new(
+	Back,
+	"back",
+	"Go back to the previous list.",
+	"back",
+	&[".."],
+	"Leave this list and go back to the one it was opened from.",
+	),
+	new(
 	Help,
 	"help",
 	"Show available actions.",
@@ -361,6 +745,55 @@ pub fn default_artist_handlers() -> Vec<Handler<ArtistCmd>> {
             &["f"],
             "Follow an artist.",
         ),
+        new(
+            Unfollow,
+            "unfollow",
+            "Unfollow an artist.",
+            "unfollow <N>",
+            &["uf"],
+            "Unfollow an artist.",
+        ),
+        new(
+            Top,
+            "top",
+            "Show the top tracks of an artist.",
+            "top <N>",
+            &["t"],
+            "Show the top tracks of an artist to play, queue or save them.",
+        ),
+        new(
+            Albums,
+            "albums",
+            "Show the albums of an artist.",
+            "albums <N> [--type album,single,compilation,appears_on]",
+            &["alb"],
+            "Show the discography of an artist to play, queue or save albums.
+By default every kind of release is listed; use --type to only show some of them.",
+        ),
+        new(
+            Related,
+            "related",
+            "Show artists similar to an artist.",
+            "related <N>",
+            &["rel"],
+            "Show artists similar to an artist.",
+        ),
+        new(
+            More,
+            "more",
+            "Load more artists.",
+            "more",
+            &["m"],
+            "Load the next page of artists, if there is one.",
+        ),
+        new(
+            Back,
+            "back",
+            "Go back to the previous list.",
+            "back",
+            &[".."],
+            "Leave this list and go back to the one it was opened from.",
+        ),
         new(
             Help,
             "help",
@@ -400,6 +833,39 @@ pub fn default_album_handlers() -> Vec<Handler<AlbumCmd>> {
             &["q", "que"],
             "Add an albums tracks to your playing Queue.",
         ),
+        new(
+            Unsave,
+            "unsave",
+            "Remove an album from your library.",
+            "unsave <N>",
+            &["unfav", "unfave"],
+            "Remove an album from your library.",
+        ),
+        new(
+            Tracks,
+            "tracks",
+            "Show the tracklist of an album.",
+            "tracks <N>",
+            &["tl"],
+            "Show the tracklist of an album with track numbers, durations and explicit tracks.
+From there you can play the album starting at a track, or queue, like and save single tracks.",
+        ),
+        new(
+            More,
+            "more",
+            "Load more albums.",
+            "more",
+            &["m"],
+            "Load the next page of albums, if there is one.",
+        ),
+        new(
+            Back,
+            "back",
+            "Go back to the previous list.",
+            "back",
+            &[".."],
+            "Leave this list and go back to the one it was opened from.",
+        ),
         new(
             Help,
             "help",
@@ -419,9 +885,9 @@ pub fn default_playlist_handlers() -> Vec<Handler<PlaylistCmd>> {
             Play,
             "play",
             "Play a playlist.",
-            "play <N>",
+            "play <N> [from <K>]",
             &["pl"],
-            "Play a playlist.\nYou can also just enter the items number.",
+            "Play a playlist, optionally starting at the K'th track.\nYou can also just enter the items number.",
         ),
         new(
             Follow,
@@ -431,6 +897,149 @@ pub fn default_playlist_handlers() -> Vec<Handler<PlaylistCmd>> {
             &["f"],
             "Follow a playlist.",
         ),
+        new(
+            Unfollow,
+            "unfollow",
+            "Unfollow a playlist.",
+            "unfollow <N>",
+            &["uf"],
+            "Unfollow a playlist.\nUnfollowing a playlist you own deletes it.",
+        ),
+        new(
+            Tracks,
+            "tracks",
+            "Show the tracks of a playlist.",
+            "tracks <N>",
+            &["tl"],
+            "Show the tracks of a playlist to play, queue, like or save them.",
+        ),
+        new(
+            Clone,
+            "clone",
+            "Copy a playlist into a new playlist of yours.",
+            "clone <N> [name]",
+            &["cl"],
+            "Create a new playlist with the tracks of a playlist.\nThe copy has the same name unless a name is given.",
+        ),
+        new(
+            Back,
+            "back",
+            "Go back to the previous list.",
+            "back",
+            &[".."],
+            "Leave this list and go back to the one it was opened from.",
+        ),
+        new(
+            Help,
+            "help",
+            "Show help about a topic.",
+            "help [topic]",
+            &[],
+            "Show help about a topic or list available commands.",
+        ),
+    ]
+}
+
+pub fn default_show_handlers() -> Vec<Handler<ShowCmd>> {
+    use ShowCmd::*;
+    let new = Handler::new;
+    vec![
+        new(
+            Play,
+            "play",
+            "Play a show.",
+            "play <N>",
+            &["pl"],
+            "Play a show.\nYou can also just enter the items number.",
+        ),
+        new(
+            Episodes,
+            "episodes",
+            "Show the episodes of a show.",
+            "episodes <N>",
+            &["eps", "tl"],
+            "Show the latest episodes of a show with their release dates and how far you listened.
+From there you can play, resume, queue or save single episodes.",
+        ),
+        new(
+            Save,
+            "save",
+            "Save a show to your library.",
+            "save <N>",
+            &["fave", "fav"],
+            "Save a show to your library.",
+        ),
+        new(
+            Unsave,
+            "unsave",
+            "Remove a show from your library.",
+            "unsave <N>",
+            &["unfav", "unfave"],
+            "Remove a show from your library.",
+        ),
+        new(
+            Back,
+            "back",
+            "Go back to the previous list.",
+            "back",
+            &[".."],
+            "Leave this list and go back to the one it was opened from.",
+        ),
+        new(
+            Help,
+            "help",
+            "Show help about a topic.",
+            "help [topic]",
+            &[],
+            "Show help about a topic or list available commands.",
+        ),
+    ]
+}
+
+pub fn default_episode_handlers() -> Vec<Handler<EpisodeCmd>> {
+    use EpisodeCmd::*;
+    let new = Handler::new;
+    vec![
+        new(
+            Play,
+            "play",
+            "Play an episode from the beginning.",
+            "play <N>",
+            &["pl"],
+            "Play an episode from the beginning.\nYou can also just enter the items number.",
+        ),
+        new(
+            Resume,
+            "resume",
+            "Continue an episode where you left off.",
+            "resume <N>",
+            &["res"],
+            "Play an episode from where you stopped listening to it.",
+        ),
+        new(
+            Queue,
+            "queue",
+            "Add an episode to your playing queue.",
+            "queue <N>",
+            &["q", "que"],
+            "Add an episode to your playing queue.",
+        ),
+        new(
+            Save,
+            "save",
+            "Save an episode to Your Episodes.",
+            "save <N>",
+            &["fave", "fav"],
+            "Save an episode to Your Episodes in your library.",
+        ),
+        new(
+            Back,
+            "back",
+            "Go back to the previous list.",
+            "back",
+            &[".."],
+            "Leave this list and go back to the one it was opened from.",
+        ),
         new(
             Help,
             "help",
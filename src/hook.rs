@@ -0,0 +1,96 @@
+use crate::watcher::{Event, Snapshot};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    path::PathBuf,
+    process::{Command, Stdio},
+    thread,
+};
+
+/// Something to do when the playback changes, configured in the config file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Hook {
+    /// The events that trigger the hook, all of them if empty.
+    #[serde(default)]
+    pub on: Vec<Event>,
+    /// A shell command to run in the background, the playback is passed in `LIBMAN_*` env variables.
+    pub run: Option<String>,
+    /// A file to append a json line to.
+    pub append: Option<PathBuf>,
+}
+
+#[derive(Serialize)]
+struct Line<'a> {
+    event: Event,
+    time: String,
+    #[serde(flatten)]
+    playback: Option<&'a Snapshot>,
+}
+
+impl Hook {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.run.is_none() && self.append.is_none() {
+            Err(String::from("a hook needs either run or append"))
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn wants(&self, event: Event) -> bool {
+        self.on.is_empty() || self.on.contains(&event)
+    }
+
+    pub fn fire(&self, event: Event, playback: Option<&Snapshot>) -> Result<(), failure::Error> {
+        if let Some(cmd) = self.run.as_ref() {
+            let mut c = if cfg!(windows) {
+                let mut c = Command::new("cmd");
+                c.arg("/C");
+                c
+            } else {
+                let mut c = Command::new("sh");
+                c.arg("-c");
+                c
+            };
+            c.arg(cmd)
+                .stdin(Stdio::null())
+                .env("LIBMAN_EVENT", event.as_str());
+            if let Some(p) = playback {
+                c.env("LIBMAN_URI", &p.uri)
+                    .env("LIBMAN_NAME", &p.name)
+                    .env("LIBMAN_ARTISTS", &p.artists)
+                    .env("LIBMAN_ALBUM", &p.album)
+                    .env("LIBMAN_DURATION_MS", p.duration_ms.to_string())
+                    .env("LIBMAN_PROGRESS_MS", p.progress_ms.to_string())
+                    .env("LIBMAN_STATE", if p.playing { "playing" } else { "paused" })
+                    .env("LIBMAN_CONTEXT", p.context.as_deref().unwrap_or_default())
+                    .env("LIBMAN_DEVICE", &p.device)
+                    .env("LIBMAN_VOLUME", p.volume.to_string());
+            }
+            // the watcher doesn't wait for slow commands, and their output would garble the prompt
+            let mut child = c
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()
+                .map_err(|e| failure::format_err!("{}: {}", cmd, e))?;
+            thread::spawn(move || child.wait());
+        }
+
+        if let Some(path) = self.append.as_ref() {
+            let line = serde_json::to_string(&Line {
+                event,
+                time: Local::now().to_rfc3339(),
+                playback,
+            })?;
+            let mut f = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|e| failure::format_err!("{}: {}", path.display(), e))?;
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,58 @@
+use crate::playlist::{self, Playlist};
+use rspotify::{blocking::client::Spotify, model::track::FullTrack};
+use std::collections::HashMap;
+
+/// Maps tracks to their positions in a set of playlists.
+pub struct LibraryIndex {
+    playlists: Vec<Playlist>,
+    // track key -> (index into playlists, position in the playlist)
+    tracks: HashMap<String, Vec<(usize, usize)>>,
+}
+
+impl LibraryIndex {
+    pub fn build(
+        client: &Spotify,
+        user_id: &str,
+        playlists: Vec<Playlist>,
+    ) -> Result<Self, failure::Error> {
+        let mut tracks = HashMap::<String, Vec<(usize, usize)>>::new();
+        for (i, pl) in playlists.iter().enumerate() {
+            for (pos, item) in pl.all_tracks(client, user_id)?.iter().enumerate() {
+                if let Some(t) = item.track.as_ref() {
+                    tracks
+                        .entry(playlist::track_key(t).to_string())
+                        .or_default()
+                        .push((i, pos));
+                }
+            }
+        }
+
+        Ok(Self { playlists, tracks })
+    }
+
+    /// Whether the index was built from these versions of the playlists.
+    pub fn is_current(&self, playlists: &[Playlist]) -> bool {
+        self.playlists.len() == playlists.len()
+            && self
+                .playlists
+                .iter()
+                .zip(playlists)
+                .all(|(a, b)| a.id() == b.id() && a.snapshot_id() == b.snapshot_id())
+    }
+
+    /// Returns the playlists containing the track, with the positions of the track in each.
+    pub fn find(&self, track: &FullTrack) -> Vec<(&Playlist, Vec<usize>)> {
+        let mut found: Vec<(&Playlist, Vec<usize>)> = Vec::new();
+        if let Some(v) = self.tracks.get(playlist::track_key(track)) {
+            for &(i, pos) in v {
+                match found.last_mut() {
+                    Some((pl, positions)) if pl.id() == self.playlists[i].id() => {
+                        positions.push(pos)
+                    }
+                    _ => found.push((&self.playlists[i], vec![pos])),
+                }
+            }
+        }
+        found
+    }
+}
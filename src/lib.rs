@@ -1,16 +1,62 @@
+pub mod api;
+pub mod auth;
 pub mod command;
+pub mod config;
 pub mod control;
 pub mod handler;
+pub mod hook;
+pub mod index;
+pub mod listens;
 pub mod playlist;
+pub mod schedule;
 pub mod search;
+pub mod smart;
+pub mod state;
+pub mod task;
+pub mod watcher;
 
 use itertools::Itertools;
 use rspotify::model::artist::SimplifiedArtist;
-use std::io::{self, BufRead, Write};
+use std::{
+    io::{self, BufRead, Write},
+    mem,
+    sync::atomic::{AtomicBool, Ordering},
+};
 
 pub type SpotifyResult = ::std::result::Result<(), failure::Error>;
 
+/// Cleared while the scheduler runs, there is nobody to answer the prompts then.
+static INTERACTIVE: AtomicBool = AtomicBool::new(true);
+/// Set when a prompt was skipped because nobody could answer it.
+static SKIPPED_PROMPT: AtomicBool = AtomicBool::new(false);
+
+pub fn set_interactive(interactive: bool) {
+    INTERACTIVE.store(interactive, Ordering::SeqCst);
+}
+
+pub fn is_interactive() -> bool {
+    INTERACTIVE.load(Ordering::SeqCst)
+}
+
+/// Whether a prompt was skipped since the last call, so the command that asked can be failed.
+pub fn skipped_prompt() -> bool {
+    SKIPPED_PROMPT.swap(false, Ordering::SeqCst)
+}
+
+/// When running unattended, prompts give up as if cancelled instead of waiting for input.
+fn unattended(msg: &str) -> bool {
+    if is_interactive() {
+        return false;
+    }
+    println!("{}: can't ask for input when running unattended", msg);
+    SKIPPED_PROMPT.store(true, Ordering::SeqCst);
+    true
+}
+
 pub fn read_input(msg: &str) -> String {
+    if unattended(msg) {
+        return String::new();
+    }
     print!("{}: ", msg);
     io::stdout().flush().ok();
     io::stdin()
@@ -32,6 +78,9 @@ pub fn read_option(msg: &str) -> Option<String> {
 }
 
 pub fn prompt(msg: &str) -> String {
+    if unattended(msg) {
+        return String::new();
+    }
     print!("{} ", msg);
     io::stdout().flush().ok();
     io::stdin()
@@ -44,6 +93,9 @@ pub fn prompt(msg: &str) -> String {
 }
 
 pub fn read_bool(msg: &str) -> bool {
+    if unattended(msg) {
+        return false;
+    }
     let msg = format!("{} [y/n]", msg);
     loop {
         let s = read_input(&msg);
@@ -107,6 +159,65 @@ pub fn split_command(s: &str) -> (&str, Option<&str>) {
     (&s[..first_space], arg)
 }
 
+/// Splits on whitespace, double quoted parts are kept as a single argument.
+pub fn split_args(s: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut buf = String::new();
+    let mut quoted = false;
+    let mut started = false;
+
+    for c in s.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                started = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if started {
+                    args.push(mem::take(&mut buf));
+                    started = false;
+                }
+            }
+            c => {
+                buf.push(c);
+                started = true;
+            }
+        }
+    }
+    if started {
+        args.push(buf);
+    }
+
+    args
+}
+
+/// Parses a selection like `0,3,7-9`, returns `None` if `s` is not in that form.
+pub fn parse_indices(s: &str) -> Option<Vec<usize>> {
+    let mut indices = Vec::new();
+    for part in s
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|p| !p.is_empty())
+    {
+        if let Some((start, end)) = part.split_once('-') {
+            let start = start.parse::<usize>().ok()?;
+            let end = end.parse::<usize>().ok()?;
+            if start > end {
+                return None;
+            }
+            indices.extend(start..=end);
+        } else {
+            indices.push(part.parse::<usize>().ok()?);
+        }
+    }
+
+    if indices.is_empty() {
+        None
+    } else {
+        Some(indices)
+    }
+}
+
+/// Reads a number between `min` and `max`, both included.
 pub fn read_number(min: usize, max: usize) -> Option<usize> {
     let msg = format!("[{}-{}, blank to cancel]>", min, max);
     loop {
@@ -114,10 +225,10 @@ pub fn read_number(min: usize, max: usize) -> Option<usize> {
         if input.is_empty() {
             return None;
         }
-        if let Ok(n) = input.parse::<usize>() {
-            return Some(n);
-        } else {
-            println!("invalid input, please enter again");
+        match input.parse::<usize>() {
+            Ok(n) if (min..=max).contains(&n) => return Some(n),
+            Ok(_) => println!("please enter a number between {} and {}", min, max),
+            Err(_) => println!("invalid input, please enter again"),
         }
     }
 }
@@ -145,6 +256,16 @@ pub fn join_artists(artists: &[SimplifiedArtist]) -> String {
     }
 }
 
+/// Formats a duration in milliseconds as `m:ss`, or `h:mm:ss` if it's long enough.
+pub fn format_duration(ms: u32) -> String {
+    let secs = ms / 1000;
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
 pub fn equalfold(a: &str, b: &str) -> bool {
     if a.len() != b.len() {
         false
@@ -161,3 +282,74 @@ pub fn equalfold(a: &str, b: &str) -> bool {
 pub fn is_digits(s: &str) -> bool {
     s.chars().all(|c| c.is_digit(10))
 }
+
+/// A track by `artist`, released in 2000, with `id` as its id and in its uri.
+#[cfg(test)]
+pub fn test_track(id: &str, artist: &str) -> rspotify::model::track::FullTrack {
+    let artist = serde_json::json!({
+        "external_urls": {},
+        "href": null,
+        "id": null,
+        "name": artist,
+        "type": "artist",
+        "uri": null,
+    });
+    serde_json::from_value(serde_json::json!({
+        "album": {
+            "album_type": "album",
+            "artists": [artist],
+            "external_urls": {},
+            "href": null,
+            "id": "album",
+            "images": [],
+            "name": "album",
+            "release_date": "2000-01-01",
+            "type": "album",
+            "uri": "spotify:album:album",
+        },
+        "artists": [artist],
+        "disc_number": 1,
+        "duration_ms": 180_000,
+        "explicit": false,
+        "external_ids": {},
+        "external_urls": {},
+        "href": null,
+        "id": id,
+        "is_local": false,
+        "name": id,
+        "popularity": 50,
+        "preview_url": null,
+        "track_number": 1,
+        "type": "track",
+        "uri": format!("spotify:track:{}", id),
+    }))
+    .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_quoted_args() {
+        assert_eq!(split_args("a  b\tc"), vec!["a", "b", "c"]);
+        assert_eq!(
+            split_args(r#"merge "Road Trip" gym "#),
+            vec!["merge", "Road Trip", "gym"]
+        );
+        // quotes can be part of a word and empty quotes are an empty argument
+        assert_eq!(split_args(r#"a"b c"d """#), vec!["ab cd", ""]);
+        assert!(split_args("   ").is_empty());
+    }
+
+    #[test]
+    fn indices_and_ranges() {
+        assert_eq!(parse_indices("3"), Some(vec![3]));
+        assert_eq!(parse_indices("0,2 5-7"), Some(vec![0, 2, 5, 6, 7]));
+        assert_eq!(parse_indices("4-4, 1"), Some(vec![4, 1]));
+        assert_eq!(parse_indices(""), None);
+        assert_eq!(parse_indices("5-3"), None);
+        assert_eq!(parse_indices("1,x"), None);
+        assert_eq!(parse_indices("-2"), None);
+    }
+}
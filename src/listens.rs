@@ -0,0 +1,265 @@
+//! A local log of the tracks you listen to, kept while libman is open.
+
+use crate::watcher::{Event, Snapshot, INTERVAL};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs::{self, OpenOptions},
+    hash::Hash,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// Turns on the listening log, configured in the config file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ListeningLog {
+    /// A json lines file.
+    pub path: PathBuf,
+    /// Seconds a track must be played for to be logged.
+    #[serde(default = "default_threshold")]
+    pub threshold: u32,
+}
+
+fn default_threshold() -> u32 {
+    30
+}
+
+/// One track or episode that was played.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Listen {
+    /// When it stopped playing.
+    pub time: DateTime<Local>,
+    pub uri: String,
+    pub name: String,
+    pub artists: Vec<String>,
+    pub album: String,
+    pub context: Option<String>,
+    pub device: String,
+    pub played_ms: u32,
+    pub duration_ms: u32,
+    /// Whether it stopped before the end.
+    pub skipped: bool,
+}
+
+impl ListeningLog {
+    /// The listen to log for a playback event, if the event ended one.
+    pub fn listen(
+        &self,
+        event: Event,
+        old: Option<&Snapshot>,
+        new: Option<&Snapshot>,
+    ) -> Option<Listen> {
+        let ended = event == Event::Track || (event == Event::Pause && new.is_none());
+        let old = old?;
+        if !ended || old.progress_ms < self.threshold * 1000 {
+            return None;
+        }
+        // the progress is from the last check, up to an interval before the track changed
+        let margin = 2 * INTERVAL.as_millis() as u32;
+        Some(Listen {
+            time: Local::now(),
+            uri: old.uri.clone(),
+            name: old.name.clone(),
+            artists: old.artist_names.clone(),
+            album: old.album.clone(),
+            context: old.context.clone(),
+            device: old.device.clone(),
+            played_ms: old.progress_ms,
+            duration_ms: old.duration_ms,
+            skipped: old.progress_ms + margin < old.duration_ms,
+        })
+    }
+}
+
+pub fn append(path: &Path, l: &Listen) -> Result<(), failure::Error> {
+    let line = serde_json::to_string(l)?;
+    let mut f = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| failure::format_err!("{}: {}", path.display(), e))?;
+    writeln!(f, "{}", line)?;
+    Ok(())
+}
+
+/// Reads the log, a missing file means nothing was logged yet.
+pub fn load(path: &Path) -> Result<Vec<Listen>, failure::Error> {
+    let s = match fs::read_to_string(path) {
+        Ok(s) => s,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(failure::format_err!("{}: {}", path.display(), e)),
+    };
+    s.lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .map(|(i, l)| {
+            serde_json::from_str(l)
+                .map_err(|e| failure::format_err!("{}:{}: {}", path.display(), i + 1, e))
+        })
+        .collect()
+}
+
+/// Play and skip counts of a group of listens.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Count {
+    pub plays: usize,
+    pub skips: usize,
+}
+
+impl Count {
+    pub fn skip_rate(&self) -> f64 {
+        if self.plays == 0 {
+            0.0
+        } else {
+            self.skips as f64 * 100.0 / self.plays as f64
+        }
+    }
+}
+
+/// Groups the listens by the keys `f` returns, most played first.
+pub fn count_by<K, F, I>(listens: &[Listen], f: F) -> Vec<(K, Count)>
+where
+    K: Eq + Hash + Ord,
+    F: Fn(&Listen) -> I,
+    I: IntoIterator<Item = K>,
+{
+    let mut counts = HashMap::new();
+    for l in listens {
+        for k in f(l) {
+            let c: &mut Count = counts.entry(k).or_default();
+            c.plays += 1;
+            if l.skipped {
+                c.skips += 1;
+            }
+        }
+    }
+    let mut counts = counts.into_iter().collect::<Vec<_>>();
+    counts.sort_by(|(a, x), (b, y)| y.plays.cmp(&x.plays).then_with(|| a.cmp(b)));
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log() -> ListeningLog {
+        ListeningLog {
+            path: PathBuf::new(),
+            threshold: 30,
+        }
+    }
+
+    fn snapshot(uri: &str, progress_ms: u32) -> Snapshot {
+        Snapshot {
+            progress_ms,
+            ..Snapshot::test(uri)
+        }
+    }
+
+    fn listen(artists: &[&str], skipped: bool) -> Listen {
+        Listen {
+            time: Local::now(),
+            uri: String::from("uri"),
+            name: String::from("name"),
+            artists: artists.iter().map(|s| s.to_string()).collect(),
+            album: String::from("album"),
+            context: None,
+            device: String::from("device"),
+            played_ms: 60_000,
+            duration_ms: 180_000,
+            skipped,
+        }
+    }
+
+    #[test]
+    fn logs_when_the_track_changes() {
+        let old = snapshot("a", 60_000);
+        let new = snapshot("b", 0);
+        let l = log().listen(Event::Track, Some(&old), Some(&new)).unwrap();
+        assert_eq!(l.uri, "a");
+        assert_eq!(l.artists, vec!["a", "b"]);
+        assert_eq!(l.played_ms, 60_000);
+        assert!(l.skipped);
+        assert!(log().listen(Event::Track, None, Some(&new)).is_none());
+    }
+
+    #[test]
+    fn logs_when_the_playback_stops() {
+        let old = snapshot("a", 60_000);
+        assert!(log().listen(Event::Pause, Some(&old), None).is_some());
+        // only paused, it may be resumed
+        let paused = snapshot("a", 60_000);
+        assert!(log()
+            .listen(Event::Pause, Some(&old), Some(&paused))
+            .is_none());
+        assert!(log()
+            .listen(Event::Resume, Some(&old), Some(&paused))
+            .is_none());
+        assert!(log()
+            .listen(Event::Device, Some(&old), Some(&paused))
+            .is_none());
+    }
+
+    #[test]
+    fn ignores_short_plays() {
+        let new = snapshot("b", 0);
+        assert!(log()
+            .listen(Event::Track, Some(&snapshot("a", 29_999)), Some(&new))
+            .is_none());
+        assert!(log()
+            .listen(Event::Track, Some(&snapshot("a", 30_000)), Some(&new))
+            .is_some());
+    }
+
+    #[test]
+    fn played_to_the_end_within_the_margin() {
+        let new = snapshot("b", 0);
+        let margin = 2 * INTERVAL.as_millis() as u32;
+        let near_end = snapshot("a", 180_000 - margin);
+        assert!(
+            !log()
+                .listen(Event::Track, Some(&near_end), Some(&new))
+                .unwrap()
+                .skipped
+        );
+        let before = snapshot("a", 180_000 - margin - 1);
+        assert!(
+            log()
+                .listen(Event::Track, Some(&before), Some(&new))
+                .unwrap()
+                .skipped
+        );
+    }
+
+    #[test]
+    fn counts_by_key() {
+        let listens = vec![
+            listen(&["b"], false),
+            listen(&["a", "b"], true),
+            listen(&["a"], false),
+            listen(&["c"], true),
+            listen(&["b"], true),
+        ];
+        let counts = count_by(&listens, |l| l.artists.clone())
+            .into_iter()
+            .map(|(k, c)| (k, c.plays, c.skips))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            counts,
+            vec![
+                (String::from("b"), 3, 2),
+                (String::from("a"), 2, 1),
+                (String::from("c"), 1, 1),
+            ]
+        );
+        assert!(count_by(&[], |l| l.artists.clone()).is_empty());
+    }
+
+    #[test]
+    fn skip_rate() {
+        assert_eq!(Count::default().skip_rate(), 0.0);
+        assert_eq!(Count { plays: 4, skips: 1 }.skip_rate(), 25.0);
+    }
+}
@@ -1,9 +1,5 @@
-use libman::{control::Controller, handler};
-use rspotify::blocking::{
-    client::Spotify,
-    oauth2::{SpotifyClientCredentials, SpotifyOAuth},
-    util::get_token,
-};
+use libman::{auth::Session, config::Config, control::Controller, handler, schedule::SystemClock};
+use rspotify::blocking::oauth2::SpotifyOAuth;
 use std::{env, path::PathBuf, process};
 
 const SCOPES: &str = "user-read-recently-played user-read-playback-state user-top-read playlist-modify-public user-modify-playback-state playlist-modify-private user-follow-modify user-read-currently-playing user-follow-read user-library-modify user-read-playback-position playlist-read-private user-library-read playlist-read-collaborative";
@@ -24,8 +20,23 @@ fn main() {
     let cache_path: PathBuf = env::var("LIBMAN_CACHE_PATH")
         .unwrap_or_else(|_| String::from("./"))
         .into();
+    let config_path: PathBuf = env::var("LIBMAN_CONFIG")
+        .unwrap_or_else(|_| String::from("./libman.json"))
+        .into();
+    let schedule_path: PathBuf = env::var("LIBMAN_SCHEDULE")
+        .unwrap_or_else(|_| String::from("./libman_schedule.json"))
+        .into();
+    let state_path: PathBuf = env::var("LIBMAN_STATE")
+        .unwrap_or_else(|_| String::from("./libman_state.json"))
+        .into();
+    let run_schedules = env::args().skip(1).any(|a| a == "--schedule");
+
+    let config = Config::load(&config_path).unwrap_or_else(|e| {
+        println!("error loading the config: {}", e);
+        process::exit(2);
+    });
 
-    let mut oauth = SpotifyOAuth::default()
+    let oauth = SpotifyOAuth::default()
         .scope(SCOPES)
         .client_id(&client_id)
         .client_secret(&client_secret)
@@ -33,22 +44,14 @@ fn main() {
         .cache_path(cache_path)
         .build();
 
-    let client = match get_token(&mut oauth) {
-        Some(token_info) => {
-            let client_credential = SpotifyClientCredentials::default()
-                .client_id(&client_id)
-                .client_secret(&client_secret)
-                .token_info(token_info)
-                .build();
-            Spotify::default()
-                .client_credentials_manager(client_credential)
-                .build()
-        }
-        None => {
-            println!("auth failed");
-            process::exit(2);
-        }
-    };
+    let session = Session::new(oauth).unwrap_or_else(|| {
+        println!("auth failed");
+        process::exit(2);
+    });
+    let client = session.client().unwrap_or_else(|e| {
+        println!("auth failed: {}", e);
+        process::exit(2);
+    });
 
     let user = client.current_user().unwrap_or_else(|e| {
         println!("error getting current user: {}", e);
@@ -64,11 +67,25 @@ fn main() {
     );
 
     let mut controller = Controller::new(
-        client,
+        session,
         user.id,
         handler::default_handlers(),
         "@libman>".to_string(),
-    );
+        config,
+        schedule_path,
+        state_path,
+    )
+    .unwrap_or_else(|e| {
+        println!("auth failed: {}", e);
+        process::exit(2);
+    });
 
-    controller.start();
+    if run_schedules {
+        if let Err(e) = controller.run_schedules(&SystemClock) {
+            println!("error: {}", e);
+            process::exit(1);
+        }
+    } else {
+        controller.start();
+    }
 }
@@ -1,8 +1,10 @@
 use rspotify::blocking::client::Spotify;
 use rspotify::model::{
-    playlist::{FullPlaylist, SimplifiedPlaylist},
+    playlist::{FullPlaylist, PlaylistTrack, SimplifiedPlaylist},
+    track::FullTrack,
     user::PublicUser,
 };
+use std::{cmp::Ordering, collections::HashSet, str::FromStr};
 
 #[derive(Debug, Clone)]
 pub enum Playlist {
@@ -51,6 +53,13 @@ impl Playlist {
         }
     }
 
+    pub fn snapshot_id(&self) -> &str {
+        match self {
+            Self::Simple(p) => &p.snapshot_id[..],
+            Self::Full(p) => &p.snapshot_id[..],
+        }
+    }
+
     pub fn owner(&self) -> &PublicUser {
         match self {
             Self::Simple(p) => &p.owner,
@@ -85,3 +94,319 @@ impl Playlist {
         matches!(self, Self::Simple(_))
     }
 }
+
+impl Playlist {
+    /// Fetches every item of the playlist, not just the first page.
+    pub fn all_tracks(
+        &self,
+        client: &Spotify,
+        user_id: &str,
+    ) -> Result<Vec<PlaylistTrack>, failure::Error> {
+        let mut items = Vec::new();
+        loop {
+            let page = client.user_playlist_tracks(
+                user_id,
+                self.id(),
+                None,
+                100,
+                items.len() as u32,
+                None,
+            )?;
+            let done = page.next.is_none() || page.items.is_empty();
+            items.extend(page.items);
+            if done {
+                return Ok(items);
+            }
+        }
+    }
+}
+
+/// Whether the playlist item is the given track.
+pub fn is_track(item: &PlaylistTrack, track: &FullTrack) -> bool {
+    item.track
+        .as_ref()
+        .map(|t| t.id.eq(&track.id) || t.uri.eq(&track.uri))
+        .unwrap_or(false)
+}
+
+/// The identity of a track, used to compare tracks across playlists.
+pub fn track_key(t: &FullTrack) -> &str {
+    t.id.as_deref().unwrap_or(&t.uri)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SetOp {
+    Merge,
+    Intersect,
+    Subtract,
+}
+
+impl SetOp {
+    pub fn apply(self, a: Vec<FullTrack>, b: Vec<FullTrack>, dedup: bool) -> Vec<FullTrack> {
+        let in_b = b
+            .iter()
+            .map(|t| track_key(t).to_string())
+            .collect::<HashSet<_>>();
+
+        let mut result = match self {
+            Self::Merge => {
+                let mut v = a;
+                v.extend(b);
+                v
+            }
+            Self::Intersect => a
+                .into_iter()
+                .filter(|t| in_b.contains(track_key(t)))
+                .collect(),
+            Self::Subtract => a
+                .into_iter()
+                .filter(|t| !in_b.contains(track_key(t)))
+                .collect(),
+        };
+
+        if dedup {
+            let mut seen = HashSet::new();
+            result.retain(|t| seen.insert(track_key(t).to_string()));
+        }
+        result
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortKey {
+    Artist,
+    Album,
+    Title,
+    ReleaseDate,
+    Added,
+    Popularity,
+    Duration,
+}
+
+impl FromStr for SortKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match &s.to_lowercase()[..] {
+            "artist" => Self::Artist,
+            "album" => Self::Album,
+            "title" | "name" => Self::Title,
+            "release-date" | "release" | "date" => Self::ReleaseDate,
+            "added" | "added-at" => Self::Added,
+            "popularity" => Self::Popularity,
+            "duration" | "length" => Self::Duration,
+            _ => return Err(format!("{} is not a valid sort key", s)),
+        })
+    }
+}
+
+impl SortKey {
+    /// Compares two playlist items; items without track info always sort last.
+    pub fn compare(self, a: &PlaylistTrack, b: &PlaylistTrack, desc: bool) -> Ordering {
+        let (x, y) = match (a.track.as_ref(), b.track.as_ref()) {
+            (Some(x), Some(y)) => (x, y),
+            (Some(_), None) => return Ordering::Less,
+            (None, Some(_)) => return Ordering::Greater,
+            (None, None) => return Ordering::Equal,
+        };
+
+        let artist = |t: &FullTrack| {
+            t.artists
+                .first()
+                .map(|a| a.name.to_lowercase())
+                .unwrap_or_default()
+        };
+        let album_order = || {
+            x.album
+                .name
+                .to_lowercase()
+                .cmp(&y.album.name.to_lowercase())
+                .then(x.disc_number.cmp(&y.disc_number))
+                .then(x.track_number.cmp(&y.track_number))
+        };
+
+        let ord = match self {
+            Self::Artist => artist(x).cmp(&artist(y)).then_with(album_order),
+            Self::Album => album_order(),
+            Self::Title => x.name.to_lowercase().cmp(&y.name.to_lowercase()),
+            // release dates are `YYYY`, `YYYY-MM` or `YYYY-MM-DD` so comparing them as strings works
+            Self::ReleaseDate => x.album.release_date.cmp(&y.album.release_date),
+            Self::Added => a.added_at.cmp(&b.added_at),
+            Self::Popularity => x.popularity.cmp(&y.popularity),
+            Self::Duration => x.duration_ms.cmp(&y.duration_ms),
+        };
+
+        if desc {
+            ord.reverse()
+        } else {
+            ord
+        }
+    }
+}
+
+/// `order[i]` is the target position of the item currently at `i`.
+/// Returns the `(range_start, insert_before)` pairs to send to the reorder endpoint, in order.
+/// Items on a longest increasing subsequence are never moved, so the number of moves is minimal.
+pub fn reorder_moves(order: &[usize]) -> Vec<(usize, usize)> {
+    let keep = longest_increasing(order);
+    let mut current = order.to_vec();
+    let mut moves = Vec::new();
+
+    for (rank, _) in keep.iter().enumerate().filter(|(_, &k)| !k) {
+        let from = current.iter().position(|&r| r == rank).unwrap();
+        let to = if rank == 0 {
+            0
+        } else {
+            current.iter().position(|&r| r == rank - 1).unwrap() + 1
+        };
+        if from == to {
+            continue;
+        }
+
+        let item = current.remove(from);
+        current.insert(if from < to { to - 1 } else { to }, item);
+        moves.push((from, to));
+    }
+
+    moves
+}
+
+/// Returns which ranks are part of a longest increasing subsequence of `order`.
+fn longest_increasing(order: &[usize]) -> Vec<bool> {
+    // tails[k] is the index in `order` of the smallest tail of an increasing run of length k + 1
+    let mut tails: Vec<usize> = Vec::new();
+    let mut prev = vec![None; order.len()];
+
+    for (i, &rank) in order.iter().enumerate() {
+        let k = tails.partition_point(|&t| order[t] < rank);
+        if k > 0 {
+            prev[i] = Some(tails[k - 1]);
+        }
+        if k == tails.len() {
+            tails.push(i);
+        } else {
+            tails[k] = i;
+        }
+    }
+
+    let mut keep = vec![false; order.len()];
+    let mut cur = tails.last().copied();
+    while let Some(i) = cur {
+        keep[order[i]] = true;
+        cur = prev[i];
+    }
+    keep
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Applies the moves like the reorder endpoint would.
+    fn apply<T: Clone>(items: &[T], moves: &[(usize, usize)]) -> Vec<T> {
+        let mut items = items.to_vec();
+        for &(from, to) in moves {
+            let item = items.remove(from);
+            items.insert(if from < to { to - 1 } else { to }, item);
+        }
+        items
+    }
+
+    /// The `order` for items that should end up sorted, ties keep their current order.
+    fn order_of<T: Ord>(items: &[T]) -> Vec<usize> {
+        let mut sorted = (0..items.len()).collect::<Vec<_>>();
+        sorted.sort_by(|&a, &b| items[a].cmp(&items[b]));
+        let mut order = vec![0; items.len()];
+        for (target, current) in sorted.into_iter().enumerate() {
+            order[current] = target;
+        }
+        order
+    }
+
+    fn check_sorts<T: Ord + Clone + std::fmt::Debug>(items: &[T]) -> Vec<(usize, usize)> {
+        let moves = reorder_moves(&order_of(items));
+        let mut want = items.to_vec();
+        want.sort();
+        assert_eq!(apply(items, &moves), want, "moves: {:?}", &moves);
+        moves
+    }
+
+    fn ids(tracks: &[FullTrack]) -> Vec<&str> {
+        tracks.iter().map(track_key).collect()
+    }
+
+    #[test]
+    fn set_operations() {
+        let tracks = |ids: &[&str]| -> Vec<FullTrack> {
+            ids.iter().map(|id| crate::test_track(id, "a")).collect()
+        };
+        let (a, b) = (tracks(&["1", "2", "3", "2"]), tracks(&["2", "4"]));
+
+        let merged = SetOp::Merge.apply(a.clone(), b.clone(), false);
+        assert_eq!(ids(&merged), vec!["1", "2", "3", "2", "2", "4"]);
+        let merged = SetOp::Merge.apply(a.clone(), b.clone(), true);
+        assert_eq!(ids(&merged), vec!["1", "2", "3", "4"]);
+
+        let both = SetOp::Intersect.apply(a.clone(), b.clone(), false);
+        assert_eq!(ids(&both), vec!["2", "2"]);
+        let both = SetOp::Intersect.apply(a.clone(), b.clone(), true);
+        assert_eq!(ids(&both), vec!["2"]);
+
+        let rest = SetOp::Subtract.apply(a, b, false);
+        assert_eq!(ids(&rest), vec!["1", "3"]);
+    }
+
+    #[test]
+    fn empty() {
+        assert!(reorder_moves(&[]).is_empty());
+        assert!(reorder_moves(&[0]).is_empty());
+    }
+
+    #[test]
+    fn already_sorted() {
+        assert!(reorder_moves(&[0, 1, 2, 3, 4]).is_empty());
+    }
+
+    #[test]
+    fn reversed() {
+        let moves = check_sorts(&[5, 4, 3, 2, 1, 0]);
+        assert_eq!(moves.len(), 5);
+    }
+
+    #[test]
+    fn single_move() {
+        assert_eq!(check_sorts(&[1, 2, 3, 0]), vec![(3, 0)]);
+        assert_eq!(check_sorts(&[3, 0, 1, 2]), vec![(0, 4)]);
+    }
+
+    #[test]
+    fn duplicate_keys() {
+        check_sorts(&["b", "a", "b", "a", "c", "a"]);
+        check_sorts(&[1, 1, 1, 0, 0]);
+        // equal keys already in place don't move
+        assert!(check_sorts(&[0, 0, 1, 1]).is_empty());
+    }
+
+    #[test]
+    fn permutations_are_minimal() {
+        let perms: &[&[usize]] = &[
+            &[2, 0, 1],
+            &[1, 0, 2],
+            &[0, 2, 1],
+            &[3, 1, 4, 0, 2],
+            &[4, 0, 3, 1, 2, 6, 5],
+            &[1, 3, 5, 7, 0, 2, 4, 6],
+        ];
+        for &order in perms {
+            let moves = reorder_moves(order);
+            let lis = longest_increasing(order).iter().filter(|&&k| k).count();
+            assert_eq!(moves.len(), order.len() - lis, "order: {:?}", order);
+            // the item at `i` must land at `order[i]`
+            let mut want = vec![0; order.len()];
+            for (i, &target) in order.iter().enumerate() {
+                want[target] = i;
+            }
+            assert_eq!(apply(&(0..order.len()).collect::<Vec<_>>(), &moves), want);
+        }
+    }
+}
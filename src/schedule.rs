@@ -0,0 +1,361 @@
+use chrono::{Datelike, Local, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+use serde::{Deserialize, Serialize};
+use std::{fmt, fs, io, path::Path, thread, time::Duration};
+
+/// Where the scheduler gets the time from, so it can be driven by something other than the wall clock.
+pub trait Clock {
+    /// The current local time.
+    fn now(&self) -> NaiveDateTime;
+    fn sleep(&self, d: Duration);
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> NaiveDateTime {
+        Local::now().naive_local()
+    }
+
+    fn sleep(&self, d: Duration) {
+        thread::sleep(d);
+    }
+}
+
+/// A command to run at a set time, created with the `at` command.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Schedule {
+    pub time: NaiveTime,
+    /// Only set if the schedule runs once.
+    pub date: Option<NaiveDate>,
+    /// The days a repeating schedule runs on.
+    #[serde(default)]
+    pub days: Vec<Weekday>,
+    pub command: String,
+    pub device: Option<String>,
+    pub volume: Option<u8>,
+    /// Seconds to raise the volume over after the command runs.
+    pub fade_in: Option<u32>,
+}
+
+impl Schedule {
+    /// Runs `command` at `time` on the given days, or once at the first `time` after `now` if there are no days.
+    pub fn new(time: NaiveTime, days: Vec<Weekday>, command: String, now: NaiveDateTime) -> Self {
+        let date = if days.is_empty() {
+            let today = now.date();
+            Some(if time > now.time() {
+                today
+            } else {
+                today.succ()
+            })
+        } else {
+            None
+        };
+        Self {
+            time,
+            date,
+            days,
+            command,
+            device: None,
+            volume: None,
+            fade_in: None,
+        }
+    }
+
+    pub fn is_once(&self) -> bool {
+        self.date.is_some()
+    }
+
+    /// Whether the schedule runs after `from`, up to and including `to`.
+    pub fn is_due(&self, from: NaiveDateTime, to: NaiveDateTime) -> bool {
+        let runs_in = |d: NaiveDate| {
+            let at = d.and_time(self.time);
+            from < at && at <= to
+        };
+        match self.date {
+            Some(d) => runs_in(d),
+            None => {
+                let mut d = from.date();
+                while d <= to.date() {
+                    if self.days.contains(&d.weekday()) && runs_in(d) {
+                        return true;
+                    }
+                    d = d.succ();
+                }
+                false
+            }
+        }
+    }
+
+    /// Whether a schedule that runs once should have already run.
+    pub fn is_missed(&self, now: NaiveDateTime) -> bool {
+        self.date.map_or(false, |d| d.and_time(self.time) <= now)
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.time.format("%H:%M"))?;
+        match self.date {
+            Some(d) => write!(f, " on {}", d)?,
+            None => write!(f, " {}", days_name(&self.days))?,
+        };
+        write!(f, ": {}", &self.command)?;
+
+        let mut opts = Vec::new();
+        if let Some(dev) = self.device.as_ref() {
+            opts.push(format!("on {}", dev));
+        }
+        if let Some(v) = self.volume {
+            opts.push(format!("volume {}%", v));
+        }
+        if let Some(secs) = self.fade_in {
+            opts.push(format!("fade in {}", crate::format_duration(secs * 1000)));
+        }
+        if !opts.is_empty() {
+            write!(f, " ({})", opts.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// The outcome of one check for due schedules.
+#[derive(Debug)]
+pub struct Tick {
+    pub now: NaiveDateTime,
+    /// The schedules to run now.
+    pub due: Vec<Schedule>,
+    /// The schedules to keep, the ones that ran once are left out.
+    pub rest: Vec<Schedule>,
+}
+
+/// Finds the schedules that came due since `last`.
+pub fn tick(clock: &dyn Clock, schedules: Vec<Schedule>, last: NaiveDateTime) -> Tick {
+    let now = clock.now();
+    let due = schedules
+        .iter()
+        .filter(|s| s.is_due(last, now))
+        .cloned()
+        .collect();
+    let rest = schedules
+        .into_iter()
+        .filter(|s| !(s.is_once() && s.is_due(last, now)))
+        .collect();
+    Tick { now, due, rest }
+}
+
+const WEEKDAYS: &[Weekday] = &[
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+];
+const WEEKENDS: &[Weekday] = &[Weekday::Sat, Weekday::Sun];
+
+/// Parses `daily`, `weekdays`, `weekends` or a list of days like `mon,wed,fri`.
+pub fn parse_days(s: &str) -> Option<Vec<Weekday>> {
+    let mut days = match s {
+        "daily" => [WEEKDAYS, WEEKENDS].concat(),
+        "weekdays" => WEEKDAYS.to_vec(),
+        "weekends" => WEEKENDS.to_vec(),
+        _ => s
+            .split(',')
+            .map(|d| d.trim().parse::<Weekday>().ok())
+            .collect::<Option<Vec<_>>>()?,
+    };
+    days.sort_by_key(|d| d.num_days_from_monday());
+    days.dedup();
+    Some(days)
+}
+
+fn days_name(days: &[Weekday]) -> String {
+    if days.len() == 7 {
+        String::from("daily")
+    } else if days == WEEKDAYS {
+        String::from("on weekdays")
+    } else if days == WEEKENDS {
+        String::from("on weekends")
+    } else {
+        format!(
+            "every {}",
+            days.iter()
+                .map(|d| d.to_string().to_lowercase())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
+/// Reads the saved schedules, a missing file means there are none.
+pub fn load(path: &Path) -> Result<Vec<Schedule>, failure::Error> {
+    match fs::read_to_string(path) {
+        Ok(s) => {
+            serde_json::from_str(&s).map_err(|e| failure::format_err!("{}: {}", path.display(), e))
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(failure::format_err!("{}: {}", path.display(), e)),
+    }
+}
+
+pub fn save(path: &Path, schedules: &[Schedule]) -> Result<(), failure::Error> {
+    fs::write(path, serde_json::to_string_pretty(schedules)?)
+        .map_err(|e| failure::format_err!("{}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    /// A clock that only moves when slept on.
+    struct FakeClock(Cell<NaiveDateTime>);
+
+    impl Clock for FakeClock {
+        fn now(&self) -> NaiveDateTime {
+            self.0.get()
+        }
+
+        fn sleep(&self, d: Duration) {
+            self.0
+                .set(self.0.get() + chrono::Duration::from_std(d).unwrap());
+        }
+    }
+
+    // 2021-06-07 is a monday
+    fn at(day: u32, h: u32, m: u32, s: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd(2021, 6, day).and_hms(h, m, s)
+    }
+
+    fn time(h: u32, m: u32) -> NaiveTime {
+        NaiveTime::from_hms(h, m, 0)
+    }
+
+    fn repeating(t: NaiveTime, days: &str) -> Schedule {
+        Schedule::new(
+            t,
+            parse_days(days).unwrap(),
+            String::from("play"),
+            at(7, 0, 0, 0),
+        )
+    }
+
+    #[test]
+    fn once_runs_today_or_tomorrow() {
+        let now = at(7, 8, 0, 0);
+        let later = Schedule::new(time(9, 0), Vec::new(), String::from("play"), now);
+        assert_eq!(later.date, Some(now.date()));
+        let earlier = Schedule::new(time(7, 0), Vec::new(), String::from("play"), now);
+        assert_eq!(earlier.date, Some(now.date().succ()));
+        // the current minute has already started
+        let same = Schedule::new(time(8, 0), Vec::new(), String::from("play"), now);
+        assert_eq!(same.date, Some(now.date().succ()));
+
+        let daily = Schedule::new(time(7, 0), parse_days("daily").unwrap(), String::new(), now);
+        assert!(!daily.is_once());
+        assert_eq!(daily.date, None);
+    }
+
+    #[test]
+    fn due_window_excludes_start_and_includes_end() {
+        let s = Schedule::new(time(8, 0), Vec::new(), String::from("play"), at(7, 7, 0, 0));
+        assert!(s.is_due(at(7, 7, 59, 50), at(7, 8, 0, 0)));
+        assert!(s.is_due(at(7, 7, 59, 59), at(7, 8, 0, 5)));
+        assert!(!s.is_due(at(7, 8, 0, 0), at(7, 8, 0, 15)));
+        assert!(!s.is_due(at(7, 7, 59, 30), at(7, 7, 59, 45)));
+        // the same time on another day
+        assert!(!s.is_due(at(8, 7, 59, 50), at(8, 8, 0, 5)));
+    }
+
+    #[test]
+    fn due_across_midnight() {
+        let s = repeating(time(0, 0), "tue");
+        assert!(s.is_due(at(7, 23, 59, 50), at(8, 0, 0, 5)));
+        assert!(!s.is_due(at(6, 23, 59, 50), at(7, 0, 0, 5)));
+
+        let once = Schedule::new(
+            time(0, 0),
+            Vec::new(),
+            String::from("play"),
+            at(7, 12, 0, 0),
+        );
+        assert_eq!(once.date, Some(at(8, 0, 0, 0).date()));
+        assert!(once.is_due(at(7, 23, 59, 50), at(8, 0, 0, 5)));
+    }
+
+    #[test]
+    fn due_on_weekdays_only() {
+        let s = repeating(time(7, 30), "weekdays");
+        // monday to friday
+        for day in 7..=11 {
+            assert!(s.is_due(at(day, 7, 29, 50), at(day, 7, 30, 5)), "{}", day);
+        }
+        // the weekend
+        for day in 12..=13 {
+            assert!(!s.is_due(at(day, 7, 29, 50), at(day, 7, 30, 5)), "{}", day);
+        }
+        // a window spanning the weekend catches monday
+        assert!(s.is_due(at(12, 0, 0, 0), at(14, 8, 0, 0)));
+    }
+
+    #[test]
+    fn missed_only_if_once_and_past() {
+        let s = Schedule::new(time(8, 0), Vec::new(), String::from("play"), at(7, 7, 0, 0));
+        assert!(!s.is_missed(at(7, 7, 59, 59)));
+        assert!(s.is_missed(at(7, 8, 0, 0)));
+        assert!(s.is_missed(at(9, 0, 0, 0)));
+        assert!(!repeating(time(8, 0), "daily").is_missed(at(9, 0, 0, 0)));
+    }
+
+    #[test]
+    fn parses_days() {
+        assert_eq!(parse_days("daily").unwrap().len(), 7);
+        assert_eq!(parse_days("weekdays").unwrap(), WEEKDAYS);
+        assert_eq!(parse_days("weekends").unwrap(), WEEKENDS);
+        assert_eq!(
+            parse_days("fri,Mon, wed,mon").unwrap(),
+            vec![Weekday::Mon, Weekday::Wed, Weekday::Fri]
+        );
+        assert_eq!(parse_days("sunday").unwrap(), vec![Weekday::Sun]);
+        assert_eq!(parse_days("mon,someday"), None);
+        assert_eq!(parse_days(""), None);
+        assert_eq!(parse_days("play"), None);
+    }
+
+    #[test]
+    fn tick_runs_due_schedules_once() {
+        let clock = FakeClock(Cell::new(at(7, 7, 59, 0)));
+        let once = Schedule::new(time(8, 0), Vec::new(), String::from("once"), clock.now());
+        let daily = Schedule::new(
+            time(8, 0),
+            parse_days("daily").unwrap(),
+            String::from("daily"),
+            clock.now(),
+        );
+        let later = Schedule::new(time(9, 0), Vec::new(), String::from("later"), clock.now());
+        let mut schedules = vec![once, daily, later];
+
+        let mut last = clock.now();
+        let mut ran = Vec::new();
+        // two hours of 15 second ticks
+        for _ in 0..(2 * 60 * 4) {
+            clock.sleep(Duration::from_secs(15));
+            let t = tick(&clock, schedules, last);
+            let now = t.now;
+            ran.extend(t.due.into_iter().map(|s| (now, s.command)));
+            schedules = t.rest;
+            last = t.now;
+        }
+
+        assert_eq!(
+            ran,
+            vec![
+                (at(7, 8, 0, 0), String::from("once")),
+                (at(7, 8, 0, 0), String::from("daily")),
+                (at(7, 9, 0, 0), String::from("later")),
+            ]
+        );
+        assert_eq!(schedules.len(), 1);
+        assert_eq!(schedules[0].command, "daily");
+    }
+}
@@ -1,9 +1,17 @@
-use crate::playlist::Playlist;
+use crate::{api, playlist::Playlist};
 use rspotify::{
     blocking::client::Spotify,
-    model::{album::SimplifiedAlbum, artist::FullArtist, search::SearchResult, track::FullTrack},
+    model::{
+        album::SimplifiedAlbum,
+        artist::FullArtist,
+        page::Page,
+        search::SearchResult,
+        show::{SimplifiedEpisode, SimplifiedShow},
+        track::FullTrack,
+    },
     senum::SearchType,
 };
+use serde::Deserialize;
 
 type Result<T> = ::std::result::Result<T, failure::Error>;
 
@@ -46,6 +54,36 @@ pub fn playlists(client: &Spotify, query: &str, limit: u32) -> Result<Vec<Playli
     })
 }
 
+// shows and episodes not available in the users market come back as null
+#[derive(Deserialize)]
+struct Shows {
+    shows: Page<Option<SimplifiedShow>>,
+}
+
+#[derive(Deserialize)]
+struct Episodes {
+    episodes: Page<Option<SimplifiedEpisode>>,
+}
+
+fn search_query(query: &str, kind: &str, limit: u32) -> Vec<(&'static str, String)> {
+    vec![
+        ("q", query.to_string()),
+        ("type", kind.to_string()),
+        ("limit", limit.to_string()),
+        ("market", String::from("from_token")),
+    ]
+}
+
+pub fn shows(client: &Spotify, query: &str, limit: u32) -> Result<Vec<SimplifiedShow>> {
+    let res: Shows = api::get(client, "search", &search_query(query, "show", limit))?;
+    Ok(res.shows.items.into_iter().flatten().collect())
+}
+
+pub fn episodes(client: &Spotify, query: &str, limit: u32) -> Result<Vec<SimplifiedEpisode>> {
+    let res: Episodes = api::get(client, "search", &search_query(query, "episode", limit))?;
+    Ok(res.episodes.items.into_iter().flatten().collect())
+}
+
 pub fn track_query(s: &str) -> String {
     if s.contains("::") {
         let mut split = s.splitn(2, "::");
@@ -0,0 +1,330 @@
+use chrono::NaiveDate;
+use rspotify::model::{audio::AudioFeatures, playlist::PlaylistTrack, track::FullTrack};
+use serde::Deserialize;
+use std::{collections::BTreeMap, str::FromStr};
+
+/// A playlist whose contents are generated from rules.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SmartPlaylist {
+    pub name: String,
+    pub description: Option<String>,
+    /// Playlists to take the tracks from, all of your playlists if empty.
+    #[serde(default, rename = "in")]
+    pub in_playlists: Vec<String>,
+    #[serde(default)]
+    pub not_in: Vec<String>,
+    #[serde(default)]
+    pub artists: Vec<String>,
+    pub genre: Option<String>,
+    pub added_after: Option<NaiveDate>,
+    pub release_year: Option<Bounds>,
+    pub popularity: Option<Bounds>,
+    #[serde(default)]
+    pub features: BTreeMap<String, Bounds>,
+    pub max_length: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Bounds {
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl Bounds {
+    pub fn contains(&self, n: f64) -> bool {
+        self.min.map_or(true, |min| n >= min) && self.max.map_or(true, |max| n <= max)
+    }
+}
+
+pub const FEATURES: &[&str] = &[
+    "acousticness",
+    "danceability",
+    "energy",
+    "instrumentalness",
+    "liveness",
+    "loudness",
+    "speechiness",
+    "tempo",
+    "valence",
+];
+
+pub fn feature_value(f: &AudioFeatures, name: &str) -> Option<f64> {
+    Some(f64::from(match name {
+        "acousticness" => f.acousticness,
+        "danceability" => f.danceability,
+        "energy" => f.energy,
+        "instrumentalness" => f.instrumentalness,
+        "liveness" => f.liveness,
+        "loudness" => f.loudness,
+        "speechiness" => f.speechiness,
+        "tempo" => f.tempo,
+        "valence" => f.valence,
+        _ => return None,
+    }))
+}
+
+/// A comparison against an audio feature, like `tempo>120`.
+#[derive(Debug, Clone)]
+pub struct Condition {
+    pub feature: String,
+    op: &'static str,
+    value: f64,
+    /// How far off a value can be to be equal, half a unit of the last digit written.
+    tolerance: f64,
+}
+
+impl Condition {
+    pub fn matches(&self, f: &AudioFeatures) -> bool {
+        let n = match feature_value(f, &self.feature) {
+            Some(n) => n,
+            None => return false,
+        };
+        match self.op {
+            ">=" => n >= self.value,
+            "<=" => n <= self.value,
+            ">" => n > self.value,
+            "<" => n < self.value,
+            _ => (n - self.value).abs() <= self.tolerance,
+        }
+    }
+}
+
+impl FromStr for Condition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // the two character operators must be tried first
+        let (i, op) = [">=", "<=", ">", "<", "="]
+            .iter()
+            .find_map(|op| s.find(op).map(|i| (i, *op)))
+            .ok_or_else(|| format!("{} is not a condition like tempo>120", s))?;
+        let feature = s[..i].trim().to_lowercase();
+        if !FEATURES.contains(&&feature[..]) {
+            return Err(format!(
+                "unknown audio feature {}, expected one of {}",
+                feature,
+                FEATURES.join(", ")
+            ));
+        }
+        let value = s[i + op.len()..].trim();
+        let decimals = value.find('.').map_or(0, |i| value.len() - i - 1);
+        let tolerance = 0.5 / 10_f64.powi(decimals as i32);
+        let value = value
+            .parse::<f64>()
+            .map_err(|_| format!("{}: the value must be a number", s))?;
+
+        Ok(Self {
+            feature,
+            op,
+            value,
+            tolerance,
+        })
+    }
+}
+
+impl SmartPlaylist {
+    pub fn validate(&self) -> Result<(), String> {
+        match self.features.keys().find(|k| !FEATURES.contains(&&k[..])) {
+            Some(k) => Err(format!(
+                "{}: unknown audio feature {}, expected one of {}",
+                &self.name,
+                k,
+                FEATURES.join(", ")
+            )),
+            None => Ok(()),
+        }
+    }
+
+    pub fn needs_genres(&self) -> bool {
+        self.genre.is_some()
+    }
+
+    pub fn needs_features(&self) -> bool {
+        !self.features.is_empty()
+    }
+
+    /// Checks the rules that depend only on the track.
+    /// `genres` are the genres of the track's artists, `features` its audio features.
+    pub fn matches(
+        &self,
+        item: &PlaylistTrack,
+        genres: &[&str],
+        features: Option<&AudioFeatures>,
+    ) -> bool {
+        let t = match item.track.as_ref() {
+            Some(t) => t,
+            None => return false,
+        };
+
+        if !self.artists.is_empty()
+            && !t.artists.iter().any(|a| {
+                self.artists
+                    .iter()
+                    .any(|name| crate::equalfold(name, &a.name))
+            })
+        {
+            return false;
+        }
+
+        if let Some(g) = self.genre.as_ref() {
+            let g = g.to_lowercase();
+            if !genres.iter().any(|genre| genre.to_lowercase().contains(&g)) {
+                return false;
+            }
+        }
+
+        if let Some(date) = self.added_after {
+            if item.added_at.naive_utc().date() <= date {
+                return false;
+            }
+        }
+
+        if let Some(b) = self.release_year {
+            match release_year(t) {
+                Some(y) if b.contains(f64::from(y)) => (),
+                _ => return false,
+            }
+        }
+
+        if let Some(b) = self.popularity {
+            if !b.contains(f64::from(t.popularity)) {
+                return false;
+            }
+        }
+
+        if !self.features.is_empty() {
+            let f = match features {
+                Some(f) => f,
+                None => return false,
+            };
+            for (name, b) in &self.features {
+                match feature_value(f, name) {
+                    Some(n) if b.contains(n) => (),
+                    _ => return false,
+                }
+            }
+        }
+
+        true
+    }
+}
+
+pub fn release_year(t: &FullTrack) -> Option<u32> {
+    t.album
+        .release_date
+        .as_ref()
+        .and_then(|d| d.get(..4))
+        .and_then(|y| y.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+
+    fn features(tempo: f32, energy: f32) -> AudioFeatures {
+        AudioFeatures {
+            acousticness: 0.0,
+            analysis_url: String::new(),
+            danceability: 0.0,
+            duration_ms: 180_000,
+            energy,
+            id: String::new(),
+            instrumentalness: 0.0,
+            key: 0,
+            liveness: 0.0,
+            loudness: -5.0,
+            mode: 1.0,
+            speechiness: 0.0,
+            tempo,
+            time_signature: 4,
+            track_href: String::new(),
+            _type: String::from("audio_features"),
+            uri: String::new(),
+            valence: 0.0,
+        }
+    }
+
+    fn item(artist: &str, added_at: &str) -> PlaylistTrack {
+        PlaylistTrack {
+            added_at: added_at.parse::<DateTime<Utc>>().unwrap(),
+            added_by: None,
+            is_local: false,
+            track: Some(crate::test_track("t", artist)),
+        }
+    }
+
+    fn rules(json: serde_json::Value) -> SmartPlaylist {
+        let mut json = json;
+        json["name"] = serde_json::json!("smart");
+        serde_json::from_value(json).unwrap()
+    }
+
+    fn matches(cond: &str, f: &AudioFeatures) -> bool {
+        cond.parse::<Condition>().unwrap().matches(f)
+    }
+
+    #[test]
+    fn comparisons() {
+        let f = features(120.0, 0.8);
+        assert!(matches("tempo>100", &f));
+        assert!(!matches("tempo>120", &f));
+        assert!(matches("tempo>=120", &f));
+        assert!(matches("tempo <= 120", &f));
+        assert!(!matches("tempo<120", &f));
+        assert!(matches("Energy<0.9", &f));
+    }
+
+    #[test]
+    fn equal_within_the_written_precision() {
+        assert!(matches("tempo=120", &features(120.4, 0.0)));
+        assert!(!matches("tempo=120", &features(120.6, 0.0)));
+        assert!(matches("energy=0.5", &features(0.0, 0.54)));
+        assert!(!matches("energy=0.5", &features(0.0, 0.56)));
+        assert!(!matches("energy=0.50", &features(0.0, 0.54)));
+    }
+
+    #[test]
+    fn invalid_conditions() {
+        assert!("tempo".parse::<Condition>().is_err());
+        assert!("speed>3".parse::<Condition>().is_err());
+        assert!("tempo>fast".parse::<Condition>().is_err());
+    }
+
+    #[test]
+    fn track_rules() {
+        let it = item("Artist", "2021-06-01T00:00:00Z");
+        assert!(rules(serde_json::json!({})).matches(&it, &[], None));
+        assert!(rules(serde_json::json!({ "artists": ["artist"] })).matches(&it, &[], None));
+        assert!(!rules(serde_json::json!({ "artists": ["other"] })).matches(&it, &[], None));
+
+        let genre = rules(serde_json::json!({ "genre": "Rock" }));
+        assert!(genre.matches(&it, &["indie rock"], None));
+        assert!(!genre.matches(&it, &["jazz"], None));
+
+        assert!(rules(serde_json::json!({ "added_after": "2021-05-31" })).matches(&it, &[], None));
+        assert!(!rules(serde_json::json!({ "added_after": "2021-06-01" })).matches(&it, &[], None));
+
+        // the test track is from 2000 with a popularity of 50
+        let years = rules(serde_json::json!({ "release_year": { "min": 1990, "max": 2000 } }));
+        assert!(years.matches(&it, &[], None));
+        let popular = rules(serde_json::json!({ "popularity": { "min": 60 } }));
+        assert!(!popular.matches(&it, &[], None));
+    }
+
+    #[test]
+    fn feature_rules() {
+        let it = item("a", "2021-06-01T00:00:00Z");
+        let fast = rules(serde_json::json!({ "features": { "tempo": { "min": 110 } } }));
+        assert!(fast.matches(&it, &[], Some(&features(120.0, 0.5))));
+        assert!(!fast.matches(&it, &[], Some(&features(100.0, 0.5))));
+        // tracks without audio features can't match
+        assert!(!fast.matches(&it, &[], None));
+
+        let unknown = rules(serde_json::json!({ "features": { "speed": { "min": 1 } } }));
+        assert!(unknown.validate().is_err());
+        assert!(fast.validate().is_ok());
+    }
+}
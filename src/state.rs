@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::Path};
+
+/// Settings libman remembers between runs.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct State {
+    /// The name of the device to play on when no device is active.
+    pub preferred_device: Option<String>,
+}
+
+impl State {
+    /// Reads the state from a json file, a missing file is the same as an empty state.
+    pub fn load(path: &Path) -> Result<Self, failure::Error> {
+        match fs::read_to_string(path) {
+            Ok(s) => serde_json::from_str(&s)
+                .map_err(|e| failure::format_err!("{}: {}", path.display(), e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(failure::format_err!("{}: {}", path.display(), e)),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), failure::Error> {
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .map_err(|e| failure::format_err!("{}: {}", path.display(), e))
+    }
+}
@@ -0,0 +1,108 @@
+//! Background tasks that keep running while the REPL waits for input.
+
+use std::{
+    mem,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// Handed to the body of a task so it can notice when it's cancelled.
+#[derive(Debug, Clone)]
+pub struct Stop {
+    set: Arc<AtomicBool>,
+    reports: Arc<Mutex<Vec<String>>>,
+}
+
+impl Stop {
+    pub fn is_set(&self) -> bool {
+        self.set.load(Ordering::SeqCst)
+    }
+
+    /// Leaves a message for the prompt to print, tasks don't write to the terminal themselves.
+    pub fn report(&self, msg: String) {
+        if let Ok(mut r) = self.reports.lock() {
+            r.push(msg);
+        }
+    }
+
+    /// Sleeps for `d`, waking up early if the task is cancelled.
+    /// Returns `false` if the task was cancelled.
+    pub fn sleep(&self, d: Duration) -> bool {
+        let end = Instant::now() + d;
+        while !self.is_set() {
+            match end.checked_duration_since(Instant::now()) {
+                Some(left) if !left.is_zero() => thread::park_timeout(left),
+                _ => return true,
+            }
+        }
+        false
+    }
+}
+
+#[derive(Debug)]
+pub struct Task {
+    pub name: String,
+    /// When the task is expected to finish, if it's known.
+    pub due: Option<Instant>,
+    stop: Stop,
+    done: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+}
+
+/// Marks the task done when its thread exits, even if it panics.
+struct Done(Arc<AtomicBool>);
+
+impl Drop for Done {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+impl Task {
+    /// Runs `f` on a new thread.
+    pub fn spawn<F>(name: &str, due: Option<Instant>, f: F) -> Self
+    where
+        F: FnOnce(Stop) + Send + 'static,
+    {
+        let stop = Stop {
+            set: Arc::new(AtomicBool::new(false)),
+            reports: Arc::new(Mutex::new(Vec::new())),
+        };
+        let done = Arc::new(AtomicBool::new(false));
+        let (s, d) = (stop.clone(), Done(Arc::clone(&done)));
+        Self {
+            name: name.to_string(),
+            due,
+            stop,
+            done,
+            handle: thread::spawn(move || {
+                let _done = d;
+                f(s)
+            }),
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done.load(Ordering::SeqCst)
+    }
+
+    /// The messages the task reported since the last call.
+    pub fn take_reports(&self) -> Vec<String> {
+        self.stop
+            .reports
+            .lock()
+            .map(|mut r| mem::take(&mut *r))
+            .unwrap_or_default()
+    }
+
+    /// Asks the task to stop and waits for it.
+    pub fn cancel(self) {
+        self.stop.set.store(true, Ordering::SeqCst);
+        self.handle.thread().unpark();
+        let _ = self.handle.join();
+    }
+}
@@ -0,0 +1,234 @@
+//! Follows the playback in the background and reports what changes.
+
+use crate::{auth::Session, task::Stop};
+use rspotify::{
+    model::{context::CurrentlyPlaybackContext, PlayingItem},
+    senum::AdditionalType,
+};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// How often the playback is checked.
+pub const INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Event {
+    /// Another track or episode started.
+    Track,
+    Pause,
+    Resume,
+    /// The playback moved to another device.
+    Device,
+}
+
+impl Event {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Track => "track",
+            Self::Pause => "pause",
+            Self::Resume => "resume",
+            Self::Device => "device",
+        }
+    }
+}
+
+/// What was playing at one point.
+#[derive(Debug, Clone, Serialize)]
+pub struct Snapshot {
+    pub uri: String,
+    pub name: String,
+    /// Or the publisher, for episodes.
+    pub artists: String,
+    /// The artists one by one, for the listening log.
+    #[serde(skip)]
+    pub artist_names: Vec<String>,
+    /// Or the show, for episodes.
+    pub album: String,
+    pub duration_ms: u32,
+    pub progress_ms: u32,
+    pub playing: bool,
+    pub context: Option<String>,
+    pub device: String,
+    pub device_id: String,
+    pub volume: u32,
+}
+
+impl Snapshot {
+    /// `None` if nothing is playing.
+    pub fn from_playback(p: &CurrentlyPlaybackContext) -> Option<Self> {
+        let (uri, name, artists, artist_names, album, duration_ms) = match p.item.as_ref()? {
+            PlayingItem::Track(t) => (
+                t.uri.clone(),
+                t.name.clone(),
+                crate::join_artists(&t.artists),
+                t.artists.iter().map(|a| a.name.clone()).collect(),
+                t.album.name.clone(),
+                t.duration_ms,
+            ),
+            PlayingItem::Episode(e) => (
+                e.uri.clone(),
+                e.name.clone(),
+                e.show.publisher.clone(),
+                vec![e.show.publisher.clone()],
+                e.show.name.clone(),
+                e.duration_ms,
+            ),
+        };
+        Some(Self {
+            uri,
+            name,
+            artists,
+            artist_names,
+            album,
+            duration_ms,
+            progress_ms: p.progress_ms.unwrap_or_default(),
+            playing: p.is_playing,
+            context: p.context.as_ref().map(|c| c.uri.clone()),
+            device: p.device.name.clone(),
+            device_id: p.device.id.clone(),
+            volume: p.device.volume_percent,
+        })
+    }
+}
+
+#[cfg(test)]
+impl Snapshot {
+    /// A track by `a` and `b` playing on device `d`, a minute into its three minutes.
+    pub fn test(uri: &str) -> Self {
+        Self {
+            uri: uri.to_string(),
+            name: String::from("name"),
+            artists: String::from("a and b"),
+            artist_names: vec![String::from("a"), String::from("b")],
+            album: String::from("album"),
+            duration_ms: 180_000,
+            progress_ms: 60_000,
+            playing: true,
+            context: None,
+            device: String::from("device"),
+            device_id: String::from("d"),
+            volume: 50,
+        }
+    }
+}
+
+/// The events that happened between two snapshots.
+pub fn changes(old: Option<&Snapshot>, new: Option<&Snapshot>) -> Vec<Event> {
+    let mut events = Vec::new();
+    match (old, new) {
+        (None, Some(n)) => {
+            events.push(Event::Track);
+            if n.playing {
+                events.push(Event::Resume);
+            }
+        }
+        (Some(o), None) if o.playing => events.push(Event::Pause),
+        (Some(o), Some(n)) => {
+            if o.device_id != n.device_id {
+                events.push(Event::Device);
+            }
+            if o.uri != n.uri {
+                events.push(Event::Track);
+            }
+            if o.playing != n.playing {
+                events.push(if n.playing {
+                    Event::Resume
+                } else {
+                    Event::Pause
+                });
+            }
+        }
+        _ => (),
+    };
+    events
+}
+
+/// Polls the playback until stopped, calling `f` with each event and the snapshots before and after it.
+/// The state when the watcher starts isn't reported.
+pub fn watch<F>(session: &Session, interval: Duration, stop: &Stop, mut f: F)
+where
+    F: FnMut(Event, Option<&Snapshot>, Option<&Snapshot>),
+{
+    let mut last = None;
+    let mut first = true;
+    loop {
+        // failed requests are tried again on the next poll
+        if let Ok(p) = session.client().and_then(|c| {
+            c.current_playback(
+                None,
+                Some(vec![AdditionalType::Track, AdditionalType::Episode]),
+            )
+        }) {
+            let now = p.as_ref().and_then(Snapshot::from_playback);
+            if !first {
+                for e in changes(last.as_ref(), now.as_ref()) {
+                    f(e, last.as_ref(), now.as_ref());
+                }
+            }
+            first = false;
+            last = now;
+        }
+        if !stop.sleep(interval) {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(uri: &str, playing: bool, device_id: &str) -> Snapshot {
+        Snapshot {
+            playing,
+            device_id: device_id.to_string(),
+            ..Snapshot::test(uri)
+        }
+    }
+
+    #[test]
+    fn starting_to_play() {
+        let a = snapshot("a", true, "d");
+        assert_eq!(changes(None, Some(&a)), vec![Event::Track, Event::Resume]);
+        let paused = snapshot("a", false, "d");
+        assert_eq!(changes(None, Some(&paused)), vec![Event::Track]);
+    }
+
+    #[test]
+    fn stopping() {
+        let a = snapshot("a", true, "d");
+        assert_eq!(changes(Some(&a), None), vec![Event::Pause]);
+        let paused = snapshot("a", false, "d");
+        assert!(changes(Some(&paused), None).is_empty());
+        assert!(changes(None, None).is_empty());
+    }
+
+    #[test]
+    fn track_change() {
+        let a = snapshot("a", true, "d");
+        let b = snapshot("b", true, "d");
+        assert_eq!(changes(Some(&a), Some(&b)), vec![Event::Track]);
+        assert!(changes(Some(&a), Some(&a)).is_empty());
+    }
+
+    #[test]
+    fn pause_and_resume() {
+        let playing = snapshot("a", true, "d");
+        let paused = snapshot("a", false, "d");
+        assert_eq!(changes(Some(&playing), Some(&paused)), vec![Event::Pause]);
+        assert_eq!(changes(Some(&paused), Some(&playing)), vec![Event::Resume]);
+    }
+
+    #[test]
+    fn device_change() {
+        let a = snapshot("a", true, "d");
+        let moved = snapshot("a", true, "e");
+        assert_eq!(changes(Some(&a), Some(&moved)), vec![Event::Device]);
+        let all = snapshot("b", false, "e");
+        assert_eq!(
+            changes(Some(&a), Some(&all)),
+            vec![Event::Device, Event::Track, Event::Pause]
+        );
+    }
+}